@@ -0,0 +1,268 @@
+use mongodb::bson::doc;
+use serenity::all::{ChannelId, CreateEmbed, CreateMessage, Http, UserId};
+
+use crate::bot::tools::get_faction_cache::get_faction_cache;
+use crate::bot::tools::settle_money::contract_cost;
+use crate::bot::Secrets;
+use crate::database::structures::{Contract, FactionChannel, Status};
+use crate::database::Database;
+use crate::pricing::format_with_commas;
+use crate::torn_api::{Priority, TornAPI};
+
+/// Contract announcements. Revivers hear about every contract in `CONTRACT_CHANNEL`;
+/// each target faction hears about its own in the channel linked with `/contract link`.
+/// Failing to announce never fails the action that triggered it.
+pub struct Announcer<'a> {
+    pub http: &'a Http,
+    pub secrets: &'a Secrets,
+    pub api: &'a TornAPI,
+}
+
+impl Announcer<'_> {
+    /// A contract was just created.
+    pub async fn created(&self, contract: &Contract) {
+        match contract.status {
+            Status::Pending => self.scheduled(contract).await,
+            Status::Active => self.started(contract).await,
+            Status::Ended => {}
+        }
+    }
+
+    /// A contract was created with a start time in the future.
+    pub async fn scheduled(&self, contract: &Contract) {
+        let embed = self
+            .reviver_embed(contract, format!("📅 Upcoming contract: {}", contract.contract_name))
+            .await;
+        self.to_revivers(embed).await;
+    }
+
+    /// A contract was created to start now, or a pending contract reached its start time.
+    pub async fn started(&self, contract: &Contract) {
+        let embed = self
+            .reviver_embed(contract, format!("🟢 Contract started: {}", contract.contract_name))
+            .await;
+        self.to_revivers(embed).await;
+
+        let mut notice = CreateEmbed::new()
+            .title(format!("Revive contract started: {}", contract.contract_name))
+            .description("Revives of your faction by Cerberus Alliance are now tracked for this contract.")
+            .field("Contract ID", format!("`{}`", contract.contract_id), true)
+            .field("Started", format!("<t:{}:f>", contract.started), true)
+            .field("Min Chance", format!("{}%", contract.min_chance), true);
+        if let Some(end_time) = contract.end_time {
+            notice = notice.field("Ends", format!("<t:{end_time}:f>"), true);
+        }
+        self.to_client(contract, notice).await;
+    }
+
+    /// Coverage of an active contract was paused.
+    pub async fn paused(&self, contract: &Contract) {
+        let embed = self
+            .reviver_embed(contract, format!("⏸️ Contract paused: {}", contract.contract_name))
+            .await
+            .description("Revives don't count for this contract until it is resumed.");
+        self.to_revivers(embed).await;
+
+        let notice = CreateEmbed::new()
+            .title(format!("Revive contract paused: {}", contract.contract_name))
+            .description("Revives of your faction are not covered by this contract until it is resumed.")
+            .field("Contract ID", format!("`{}`", contract.contract_id), true);
+        self.to_client(contract, notice).await;
+    }
+
+    /// Coverage of a paused contract was resumed.
+    pub async fn resumed(&self, contract: &Contract) {
+        let embed = self
+            .reviver_embed(contract, format!("▶️ Contract resumed: {}", contract.contract_name))
+            .await;
+        self.to_revivers(embed).await;
+
+        let notice = CreateEmbed::new()
+            .title(format!("Revive contract resumed: {}", contract.contract_name))
+            .description("Revives of your faction are covered by this contract again.")
+            .field("Contract ID", format!("`{}`", contract.contract_id), true);
+        self.to_client(contract, notice).await;
+    }
+
+    /// A contract ended, by hand or at its end time.
+    pub async fn ended(&self, contract: &Contract) {
+        let embed = self
+            .reviver_embed(contract, format!("🔴 Contract ended: {}", contract.contract_name))
+            .await
+            .field("Ended", format!("<t:{}:f>", contract.ended), true);
+        self.to_revivers(embed).await;
+
+        let reviving_faction_ids = self.secrets.reviving_faction_ids();
+        let (counts, breakdown) = match contract_cost(contract, &reviving_faction_ids, self.api).await {
+            Ok(cost) => cost,
+            Err(e) => {
+                log::error!(
+                    "Failed to price contract {} for its end announcement: {e:#}",
+                    contract.contract_id
+                );
+                return;
+            }
+        };
+
+        let summary = CreateEmbed::new()
+            .title(format!("Revive contract ended: {}", contract.contract_name))
+            .description(format!(
+                "Run `/report` with contract ID `{}` for the full report.",
+                contract.contract_id
+            ))
+            .field("Successful Revives", counts.successful.to_string(), true)
+            .field("Failed Counted", counts.failed_counted.to_string(), true)
+            .field("", "", false)
+            .field("Started", format!("<t:{}:f>", contract.started), true)
+            .field("Ended", format!("<t:{}:f>", contract.ended), true)
+            .field(
+                "Final Price",
+                format!("${}", format_with_commas(breakdown.final_with_markup)),
+                true,
+            );
+        self.to_client(contract, summary).await;
+    }
+
+    /// The running cost of a contract passed `threshold` percent of its budget.
+    pub async fn budget_alert(&self, contract: &Contract, threshold: u64, spent: u64) {
+        let Some(budget) = contract.budget else {
+            return;
+        };
+        let ending = threshold >= 100 && contract.end_at_budget;
+
+        let embed = CreateEmbed::new()
+            .title(format!(
+                "💰 {}% of the budget used: {}",
+                threshold, contract.contract_name
+            ))
+            .description(if ending {
+                "The budget is reached and the contract is ended automatically."
+            } else if threshold >= 100 {
+                "The budget is reached; the contract keeps running until it is ended."
+            } else {
+                "The contract is getting close to its budget."
+            })
+            .field("Contract ID", format!("`{}`", contract.contract_id), true)
+            .field("Spent", format!("${}", format_with_commas(spent)), true)
+            .field("Budget", format!("${}", format_with_commas(budget)), true);
+
+        self.to_admins(embed.clone()).await;
+        self.to_client(contract, embed).await;
+    }
+
+    async fn reviver_embed(&self, contract: &Contract, title: String) -> CreateEmbed {
+        let target_factions = contract.target_factions();
+        let mut targets = Vec::with_capacity(target_factions.len());
+        for id in &target_factions {
+            let target = match get_faction_cache(*id, self.api, Priority::Background).await {
+                Ok(faction) => format!("{} ({})", faction.name, faction.faction_id),
+                Err(_) => id.to_string(),
+            };
+            targets.push(format!(
+                "[{target}](https://www.torn.com/factions.php?step=profile&ID={id})"
+            ));
+        }
+
+        let mut embed = CreateEmbed::new()
+            .title(title)
+            .field(
+                if targets.len() == 1 {
+                    "Target Faction"
+                } else {
+                    "Target Factions"
+                },
+                targets.join("\n"),
+                true,
+            )
+            .field("Min Chance", format!("{}%", contract.min_chance), true)
+            .field("Scope", contract.scope.label(), true)
+            .field("Pricing", contract.pricing_type.label(), true)
+            .field("Starts", format!("<t:{}:f>", contract.started), true);
+        if let Some(end_time) = contract.end_time {
+            embed = embed.field("Ends", format!("<t:{end_time}:f>"), true);
+        }
+        if let Some(budget) = contract.budget {
+            embed = embed.field("Budget", format!("${}", format_with_commas(budget)), true);
+        }
+        embed.field("Contract ID", format!("`{}`", contract.contract_id), true)
+    }
+
+    /// Sends to the ops channel mentioning the owner and every configured admin, or to
+    /// each of them directly when no ops channel is configured.
+    async fn to_admins(&self, embed: CreateEmbed) {
+        let mut recipients = vec![self.secrets.owner_id];
+        for admin in &self.secrets.admins {
+            if !recipients.contains(admin) {
+                recipients.push(*admin);
+            }
+        }
+
+        if let Some(channel) = self.secrets.ops_channel {
+            let mentions = recipients
+                .iter()
+                .map(|id| format!("<@{id}>"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let result = ChannelId::new(channel)
+                .send_message(self.http, CreateMessage::new().content(mentions).embed(embed))
+                .await;
+            if let Err(e) = result {
+                log::error!("Failed to send contract alert to admins: {e:#}");
+            }
+            return;
+        }
+
+        for recipient in recipients {
+            let result = UserId::new(recipient)
+                .direct_message(self.http, CreateMessage::new().embed(embed.clone()))
+                .await;
+            if let Err(e) = result {
+                log::error!("Failed to send contract alert to admin {recipient}: {e:#}");
+            }
+        }
+    }
+
+    async fn to_revivers(&self, embed: CreateEmbed) {
+        let Some(channel) = self.secrets.contract_channel else {
+            return;
+        };
+        self.send(ChannelId::new(channel), embed).await;
+    }
+
+    /// Sends to the linked channel of every target faction, once per channel.
+    async fn to_client(&self, contract: &Contract, embed: CreateEmbed) {
+        let target_factions: Vec<i64> = contract
+            .target_factions()
+            .iter()
+            .map(|id| *id as i64)
+            .collect();
+        let linked = Database::get_collection_with_filter::<FactionChannel>(Some(doc! {
+            "faction_id": { "$in": target_factions }
+        }))
+        .await;
+
+        match linked {
+            Ok(linked) => {
+                let mut channels: Vec<u64> = linked.iter().map(|link| link.channel_id).collect();
+                channels.sort_unstable();
+                channels.dedup();
+                for channel in channels {
+                    self.send(ChannelId::new(channel), embed.clone()).await;
+                }
+            }
+            Err(e) => log::error!(
+                "Failed to look up the channels of contract {}: {e:#}",
+                contract.contract_id
+            ),
+        }
+    }
+
+    async fn send(&self, channel: ChannelId, embed: CreateEmbed) {
+        if let Err(e) = channel
+            .send_message(self.http, CreateMessage::new().embed(embed))
+            .await
+        {
+            log::error!("Failed to send contract announcement to channel {channel}: {e:#}");
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::Utc;
+use mongodb::bson::{self, doc};
+use serenity::all::Http;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+
+use crate::bot::announcements::Announcer;
+use crate::bot::tools::contract_events::record_event;
+use crate::bot::tools::contract_lifecycle::end_contract;
+use crate::bot::tools::contract_scope::resolve_scope;
+use crate::bot::tools::settle_money::contract_revives;
+use crate::bot::Secrets;
+use crate::database::structures::{Contract, ContractEventKind, ReviveEntry, Status};
+use crate::database::Database;
+use crate::pricing::{classify_revive, ReviveClass, ReviveCounts};
+use crate::shutdown::Shutdown;
+use crate::torn_api::{Priority, TornAPI};
+
+/// Percentages of a contract's budget at which admins and the client are alerted.
+const BUDGET_ALERT_THRESHOLDS: [u64; 2] = [75, 100];
+
+/// Running cost of a contract with a budget, kept between batches so each batch only
+/// prices its own revives.
+struct RunningCost {
+    /// The contract's terms when the cost was first computed; any change prices it again
+    /// from scratch.
+    terms: Contract,
+    counts: ReviveCounts,
+    /// Time of the revive that took the cost to the budget.
+    reached_budget_at: Option<u64>,
+}
+
+impl RunningCost {
+    /// Prices every stored revive of the contract.
+    async fn compute(
+        contract: &Contract,
+        reviving_faction_ids: &[u64],
+        api: &TornAPI,
+    ) -> anyhow::Result<Self> {
+        let mut window = contract.clone();
+        window.ended = Utc::now().timestamp() as u64;
+        let revives = contract_revives(&window, reviving_faction_ids).await?;
+
+        let mut cost = Self {
+            terms: pricing_terms(contract),
+            counts: ReviveCounts {
+                successful: 0,
+                failed_counted: 0,
+            },
+            reached_budget_at: None,
+        };
+        cost.add(contract, revives, api).await?;
+        Ok(cost)
+    }
+
+    /// Adds revives of the contract, oldest first, noting when the budget is reached.
+    async fn add(
+        &mut self,
+        contract: &Contract,
+        mut revives: Vec<ReviveEntry>,
+        api: &TornAPI,
+    ) -> anyhow::Result<()> {
+        let scope = resolve_scope(contract, &revives, api, Priority::Background).await?;
+        revives.sort_by_key(|revive| revive.timestamp);
+
+        for revive in &revives {
+            match classify_revive(revive, contract.min_chance, &scope) {
+                ReviveClass::Success => self.counts.successful += 1,
+                ReviveClass::FailedCounted => self.counts.failed_counted += 1,
+                ReviveClass::Ignored | ReviveClass::OutOfScope => continue,
+            }
+            if self.reached_budget_at.is_none()
+                && contract.budget.is_some_and(|budget| self.spent(contract) >= budget)
+            {
+                self.reached_budget_at = Some(revive.timestamp);
+            }
+        }
+        Ok(())
+    }
+
+    fn spent(&self, contract: &Contract) -> u64 {
+        contract
+            .pricing_type
+            .calculate(self.counts, contract.faction_cut)
+            .final_with_markup
+    }
+}
+
+/// The contract without the fields that change as it runs, to tell whether its terms
+/// were edited since it was priced.
+fn pricing_terms(contract: &Contract) -> Contract {
+    let mut terms = contract.clone();
+    terms.budget_alerted = 0;
+    terms.revives_synced = false;
+    terms.money_settled = false;
+    terms
+}
+
+/// Whether a revive of the batch counts toward the contract's window.
+fn in_contract(contract: &Contract, revive: &ReviveEntry, reviving_faction_ids: &[u64]) -> bool {
+    reviving_faction_ids.contains(&revive.reviver_faction)
+        && contract.target_factions().contains(&revive.target_faction)
+        && revive.timestamp >= contract.started
+        && !contract.is_paused_at(revive.timestamp)
+}
+
+/// Prices contracts with a budget as their revives come in, alerts admins and the client
+/// once the running cost passes each threshold, and ends contracts set to end at their
+/// budget. Contracts are priced again from scratch after a revive backfill, which stores
+/// revives without announcing them.
+pub async fn run_budget_watch(
+    http: Arc<Http>,
+    secrets: Secrets,
+    api: Arc<TornAPI>,
+    mut revives: Receiver<Vec<ReviveEntry>>,
+    mut backfills: Receiver<u64>,
+    shutdown: Shutdown,
+) {
+    let announcer = Announcer {
+        http: &http,
+        secrets: &secrets,
+        api: &api,
+    };
+    let mut running = HashMap::new();
+
+    loop {
+        let batch = tokio::select! {
+            received = revives.recv() => match received {
+                Ok(batch) => Some(batch),
+                Err(RecvError::Lagged(skipped)) => {
+                    // The running costs are missing those revives now.
+                    log::warn!("Budget watch missed {skipped} batch(es) of revives");
+                    running.clear();
+                    continue;
+                }
+                Err(RecvError::Closed) => return,
+            },
+            received = backfills.recv() => match received {
+                Ok(faction_id) => {
+                    log::info!("Repricing contract budgets after a revive backfill of faction {faction_id}");
+                    None
+                }
+                Err(RecvError::Lagged(_)) => None,
+                Err(RecvError::Closed) => return,
+            },
+        };
+
+        let Some(_work) = shutdown.begin() else {
+            return;
+        };
+        if batch.is_none() {
+            running.clear();
+        }
+        if let Err(e) = check_budgets(&announcer, &mut running, batch.as_deref()).await {
+            // Priced again from scratch next time.
+            running.clear();
+            log::error!("Failed to check contract budgets: {e:#}");
+        }
+    }
+}
+
+/// Updates the running cost of every active contract with a budget that `batch` has
+/// revives for, or of every one when there is no batch, and acts on the thresholds it
+/// passed.
+async fn check_budgets(
+    announcer: &Announcer<'_>,
+    running: &mut HashMap<String, RunningCost>,
+    batch: Option<&[ReviveEntry]>,
+) -> anyhow::Result<()> {
+    let reviving_faction_ids = announcer.secrets.reviving_faction_ids();
+
+    let contracts: Vec<Contract> = Database::get_collection_with_filter(Some(doc! {
+        "status": bson::to_bson(&Status::Active).unwrap(),
+        "budget": { "$ne": null },
+    }))
+    .await?;
+    running.retain(|contract_id, _| {
+        contracts
+            .iter()
+            .any(|contract| &contract.contract_id == contract_id)
+    });
+
+    for mut contract in contracts {
+        let Some(budget) = contract.budget.filter(|&budget| budget > 0) else {
+            continue;
+        };
+
+        let new: Vec<ReviveEntry> = batch
+            .unwrap_or_default()
+            .iter()
+            .filter(|revive| in_contract(&contract, revive, &reviving_faction_ids))
+            .cloned()
+            .collect();
+        if batch.is_some() && new.is_empty() {
+            continue;
+        }
+
+        let cost = match running.entry(contract.contract_id.clone()) {
+            Entry::Occupied(entry) if entry.get().terms == pricing_terms(&contract) => {
+                let cost = entry.into_mut();
+                cost.add(&contract, new, announcer.api).await?;
+                cost
+            }
+            // First seen, or edited since: the batch is already stored, so pricing every
+            // stored revive includes it.
+            entry => {
+                let cost =
+                    RunningCost::compute(&contract, &reviving_faction_ids, announcer.api).await?;
+                match entry {
+                    Entry::Occupied(mut entry) => {
+                        entry.insert(cost);
+                        entry.into_mut()
+                    }
+                    Entry::Vacant(entry) => entry.insert(cost),
+                }
+            }
+        };
+
+        let spent = cost.spent(&contract);
+        let reached_budget_at = cost.reached_budget_at;
+        let percent = spent.saturating_mul(100) / budget;
+
+        // Every threshold passed since the last alert, lowest first, so a jump from
+        // under 75% to over 100% still sends the 75% alert.
+        let passed: Vec<u64> = BUDGET_ALERT_THRESHOLDS
+            .into_iter()
+            .filter(|&threshold| percent >= threshold && threshold > contract.budget_alerted)
+            .collect();
+        for &threshold in &passed {
+            contract.budget_alerted = threshold;
+            Database::update_doc::<Contract>(
+                doc! { "contract_id": &contract.contract_id },
+                doc! { "$set": { "budget_alerted": threshold as i64 } },
+            )
+            .await?;
+            record_event(
+                &contract.contract_id,
+                ContractEventKind::BudgetAlert,
+                None,
+                doc! {
+                    "threshold": threshold as i64,
+                    "spent": spent as i64,
+                    "budget": budget as i64,
+                },
+            )
+            .await;
+            log::info!(
+                "Contract {} ({}) used {threshold}% of its budget: ${spent} of ${budget}",
+                contract.contract_name,
+                contract.contract_id
+            );
+            announcer.budget_alert(&contract, threshold, spent).await;
+        }
+
+        if passed.last().is_some_and(|&threshold| threshold >= 100) && contract.end_at_budget {
+            // Revives after the one that reached the budget are not part of the contract.
+            let ended = reached_budget_at.unwrap_or_else(|| Utc::now().timestamp() as u64);
+            end_contract(&mut contract, ended, &reviving_faction_ids, announcer.api, None).await?;
+            running.remove(&contract.contract_id);
+            announcer.ended(&contract).await;
+            log::info!(
+                "Contract {} ({}) ended at its budget",
+                contract.contract_name,
+                contract.contract_id
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -1,23 +1,31 @@
+use crate::bot::announcements::Announcer;
 use crate::bot::auth::{level_of, AccessLevel};
 use crate::bot::data::{Context, Data, Error};
-use crate::bot::tools::settle_money::settle_contract_money;
-use crate::database::structures::Status;
+use crate::bot::tools::contract_edit::{
+    check_changes, describe_edits, edit_contract, resettle_if_ended, ContractChanges,
+};
+use crate::bot::tools::contract_events::{contract_terms, edit_events, record_event};
+use crate::bot::tools::get_faction_cache::get_faction_cache;
+use crate::bot::tools::contract_lifecycle::{end_contract, promote_pending_contracts};
+use crate::database::structures::{
+    Contract, ContractEdit, ContractEvent, ContractEventKind, FactionChannel, Pause, Status,
+};
 use crate::database::Database;
-use crate::pricing::PricingType;
+use crate::pricing::{ContractScope, PricingType};
+use crate::torn_api::{Priority, TornError};
 use chrono::{DateTime, NaiveDateTime, Utc};
 use mongodb::bson;
-use mongodb::bson::{doc, Document};
+use mongodb::bson::{doc, Bson, Document};
 use poise::CreateReply;
 use rand::distr::Alphanumeric;
 use rand::Rng;
 use serenity::all::{
-    ButtonStyle, ComponentInteraction, CreateActionRow, CreateButton, CreateEmbed,
+    ButtonStyle, ChannelId, ComponentInteraction, CreateActionRow, CreateButton, CreateEmbed,
     CreateEmbedFooter, CreateInteractionResponse, CreateInteractionResponseMessage, ReactionType,
     UserId,
 };
 use serenity::utils::MessageBuilder;
-use torn_api::models::FactionId;
-use torn_api::{ApiError, Error as TornError};
+use torn_api::ApiError;
 
 pub(crate) const PAGE_SIZE: u64 = 10;
 
@@ -57,15 +65,33 @@ impl From<PricingChoice> for PricingType {
     }
 }
 
+#[derive(poise::ChoiceParameter)]
+pub enum ScopeChoice {
+    #[name = "all"]
+    All,
+    #[name = "defensive"]
+    Defensive,
+    #[name = "offensive"]
+    Offensive,
+}
+
 /// Manage contracts
-#[poise::command(slash_command, subcommands("start", "end", "list"))]
+#[poise::command(slash_command, subcommands("start", "end", "edit", "pause", "resume", "list", "link", "history"))]
 pub async fn contract(_ctx: Context<'_>) -> Result<(), Error> {
     // Parent command of subcommands, never invoked directly.
     Ok(())
 }
 
+pub(crate) fn announcer<'a>(ctx: &'a Context<'_>) -> Announcer<'a> {
+    Announcer {
+        http: ctx.http(),
+        secrets: &ctx.data().secrets,
+        api: &ctx.data().torn_api,
+    }
+}
+
 /// Returns false (and replies) when the invoking user is not an admin.
-async fn ensure_admin(ctx: &Context<'_>) -> Result<bool, Error> {
+pub(crate) async fn ensure_admin(ctx: &Context<'_>) -> Result<bool, Error> {
     if level_of(ctx) >= AccessLevel::Admin {
         return Ok(true);
     }
@@ -85,6 +111,7 @@ async fn ensure_admin(ctx: &Context<'_>) -> Result<bool, Error> {
 
 /// Create a new contract
 #[poise::command(slash_command)]
+#[allow(clippy::too_many_arguments)] // one per slash command option
 pub async fn start(
     ctx: Context<'_>,
     #[description = "The name of the contract"] contract_name: String,
@@ -94,16 +121,37 @@ pub async fn start(
     #[description = "The cut the faction gets from the contract (defaults based on pricing type)"]
     faction_cut: Option<u64>,
     #[description = "Optional contract start time in UTC as YYYY-MM-DD HH:MM"] start_time: Option<String>,
+    #[description = "Optional time to end the contract automatically, in UTC as YYYY-MM-DD HH:MM"]
+    end_time: Option<String>,
+    #[description = "Other factions the contract covers, as comma separated IDs"]
+    extra_faction_ids: Option<String>,
+    #[description = "Which revives count, by hospital reason (defaults to all)"] scope: Option<ScopeChoice>,
+    #[description = "For a defensive scope: the attacking factions, as comma separated IDs"]
+    attacker_faction_ids: Option<String>,
+    #[description = "Most the client wants to spend, as the final price in $"] budget: Option<u64>,
+    #[description = "End the contract automatically once the budget is reached"] end_at_budget: Option<bool>,
 ) -> Result<(), Error> {
     if !ensure_admin(&ctx).await? {
         return Ok(());
     }
 
+    let budget = budget.filter(|&budget| budget > 0);
+    let end_at_budget = end_at_budget.unwrap_or(false);
+    if end_at_budget && budget.is_none() {
+        ctx.send(
+            CreateReply::default()
+                .content("`end_at_budget` needs a `budget`.")
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(());
+    }
+
     let pricing_type: PricingType = pricing_type.into();
     let faction_cut = faction_cut.unwrap_or_else(|| pricing_type.default_faction_cut() as u64);
 
     let started_at = match start_time {
-        Some(start_time) => match parse_contract_start_time(&start_time) {
+        Some(start_time) => match parse_contract_time(&start_time) {
             Ok(started_at) => started_at,
             Err(error) => {
                 ctx.send(CreateReply::default().content(error).ephemeral(true))
@@ -114,47 +162,126 @@ pub async fn start(
         None => Utc::now(),
     };
 
-    let status = if started_at > Utc::now() {
-        Status::Pending
-    } else {
-        Status::Active
+    let end_time = match end_time {
+        Some(end_time) => match parse_contract_time(&end_time) {
+            Ok(end_time) if end_time <= started_at.max(Utc::now()) => {
+                ctx.send(
+                    CreateReply::default()
+                        .content("The end time must be after the start time and in the future.")
+                        .ephemeral(true),
+                )
+                .await?;
+                return Ok(());
+            }
+            Ok(end_time) => Some(end_time.timestamp() as u64),
+            Err(error) => {
+                ctx.send(CreateReply::default().content(error).ephemeral(true))
+                    .await?;
+                return Ok(());
+            }
+        },
+        None => None,
     };
 
-    let faction_data = match ctx
-        .data()
-        .torn_api
-        .get_faction_basic(FactionId::new(faction_id as i32))
-        .await
-    {
-        Ok(data) => data,
-        Err(TornError::Api(ApiError::IncorrectId | ApiError::IncorrectIdEntityRelation)) => {
-            log::info!("Invalid faction ID: {faction_id}");
+    let attacker_factions = match attacker_faction_ids.as_deref().map(parse_faction_ids) {
+        Some(Ok(ids)) => ids,
+        Some(Err(error)) => {
+            ctx.send(CreateReply::default().content(error).ephemeral(true))
+                .await?;
+            return Ok(());
+        }
+        None => Vec::new(),
+    };
+
+    let scope = match (scope.unwrap_or(ScopeChoice::All), attacker_factions.is_empty()) {
+        (ScopeChoice::Defensive, false) => ContractScope::Defensive { attacker_factions },
+        (ScopeChoice::Defensive, true) => {
             ctx.send(
                 CreateReply::default()
-                    .content("Invalid faction ID")
+                    .content("A defensive contract needs `attacker_faction_ids`.")
                     .ephemeral(true),
             )
             .await?;
             return Ok(());
         }
-        Err(e) => {
-            let message = format!("Failed to fetch faction data from Torn. Please try again later. ({e:#})");
-            log::info!("{message}");
+        (_, false) => {
             ctx.send(
                 CreateReply::default()
-                    .content("Failed to fetch faction data from Torn. Please try again later.")
+                    .content("`attacker_faction_ids` only applies to a defensive contract.")
                     .ephemeral(true),
             )
             .await?;
             return Ok(());
         }
+        (ScopeChoice::All, true) => ContractScope::All,
+        (ScopeChoice::Offensive, true) => ContractScope::Offensive,
     };
-    let _ = &faction_data;
+
+    let status = if started_at > Utc::now() {
+        Status::Pending
+    } else {
+        Status::Active
+    };
+
+    let mut faction_ids = vec![faction_id];
+    if let Some(extra_faction_ids) = extra_faction_ids {
+        match parse_faction_ids(&extra_faction_ids) {
+            Ok(extra) => {
+                for id in extra {
+                    if !faction_ids.contains(&id) {
+                        faction_ids.push(id);
+                    }
+                }
+            }
+            Err(error) => {
+                ctx.send(CreateReply::default().content(error).ephemeral(true))
+                    .await?;
+                return Ok(());
+            }
+        }
+    }
+
+    for &target in &faction_ids {
+        match get_faction_cache(target, &ctx.data().torn_api, Priority::Interactive).await {
+            Ok(_) => {}
+            Err(TornError::Api(ApiError::IncorrectId | ApiError::IncorrectIdEntityRelation)) => {
+                log::info!("Invalid faction ID: {target}");
+                ctx.send(
+                    CreateReply::default()
+                        .content(format!("Invalid faction ID: {target}"))
+                        .ephemeral(true),
+                )
+                .await?;
+                return Ok(());
+            }
+            Err(TornError::Unavailable(unavailable)) => {
+                log::info!("Torn unavailable while creating contract: {unavailable}");
+                ctx.send(
+                    CreateReply::default()
+                        .content(unavailable.to_string())
+                        .ephemeral(true),
+                )
+                .await?;
+                return Ok(());
+            }
+            Err(e) => {
+                let message = format!("Failed to fetch faction data from Torn. Please try again later. ({e:#})");
+                log::info!("{message}");
+                ctx.send(
+                    CreateReply::default()
+                        .content("Failed to fetch faction data from Torn. Please try again later.")
+                        .ephemeral(true),
+                )
+                .await?;
+                return Ok(());
+            }
+        }
+    }
 
     log::info!(
-        "Processing create subcommand with contract_name: {} and faction_id: {}",
+        "Processing create subcommand with contract_name: {} and factions: {:?}",
         contract_name,
-        faction_id
+        faction_ids
     );
 
     let contract = crate::database::structures::Contract {
@@ -162,7 +289,9 @@ pub async fn start(
         contract_id: generate_contract_id().await,
         contract_name,
         faction_id,
+        faction_ids,
         min_chance,
+        scope,
         started: started_at.timestamp() as u64,
         ended: 0,
         status,
@@ -170,6 +299,11 @@ pub async fn start(
         pricing_type,
         revives_synced: false,
         money_settled: false,
+        budget,
+        end_at_budget,
+        budget_alerted: 0,
+        pauses: Vec::new(),
+        end_time,
     };
 
     let status_label = match contract.status {
@@ -178,7 +312,8 @@ pub async fn start(
         Status::Ended => "ended",
     };
 
-    let message = MessageBuilder::new()
+    let mut message = MessageBuilder::new();
+    message
         .push("Contract created with ID: ")
         .push_mono(contract.contract_id.clone())
         .push(" at ")
@@ -187,14 +322,28 @@ pub async fn start(
         .push(status_label)
         .push(" (pricing: ")
         .push(contract.pricing_type.label())
-        .push(").")
-        .build();
-
-    Database::insert(contract).await.unwrap();
+        .push(", scope: ")
+        .push(contract.scope.label())
+        .push(").");
+    if let Some(end_time) = contract.end_time {
+        message.push(format!(" It will end automatically at {}.", format_time(end_time)));
+    }
+    let message = message.build();
+
+    Database::insert(contract.clone()).await.unwrap();
+    record_event(
+        &contract.contract_id,
+        ContractEventKind::Created,
+        Some(ctx.author().id.get()),
+        contract_terms(&contract),
+    )
+    .await;
 
     ctx.send(CreateReply::default().content(message).ephemeral(true))
         .await?;
 
+    announcer(&ctx).created(&contract).await;
+
     Ok(())
 }
 
@@ -234,65 +383,29 @@ pub async fn end(
             .push("This contract has already ended.")
             .build()
     } else {
-        contract.status = Status::Ended;
-        contract.ended = Utc::now().timestamp() as u64;
-
-        Database::update(contract.clone(), doc! {"contract_id": contract_id.clone()})
-            .await
-            .unwrap();
-
         ctx.defer().await?;
 
-        let reviving_faction_ids = ctx.data().secrets.reviving_faction_ids();
-
-        let settled = settle_contract_money(&contract, &reviving_faction_ids).await;
-        if let Err(e) = &settled {
-            log::error!("Failed to settle money for contract {}: {e:#}", contract.contract_id);
-        }
-
-        let mut updated_contract = contract.clone();
-        updated_contract.money_settled = true;
-        Database::update(updated_contract, doc! {"contract_id": contract_id.clone()})
-            .await
-            .unwrap();
-
-        let unsettled: Vec<crate::database::structures::Contract> =
-            Database::get_collection_with_filter(Some(doc! {
-                "status": bson::to_bson(&Status::Ended).unwrap(),
-                "money_settled": { "$ne": true },
-                "contract_id": { "$ne": &contract.contract_id }
-            }))
-            .await
-            .unwrap();
-
-        let mut backfill_count = 0u64;
-        for old_contract in &unsettled {
-            match settle_contract_money(old_contract, &reviving_faction_ids).await {
-                Ok(count) => {
-                    backfill_count += count;
-                    let mut c = old_contract.clone();
-                    c.money_settled = true;
-                    Database::update(c, doc! {"contract_id": old_contract.contract_id.clone()})
-                        .await
-                        .unwrap();
-                }
-                Err(e) => {
-                    log::error!(
-                        "Failed to settle money for old contract {}: {e:#}",
-                        old_contract.contract_id
-                    );
-                }
-            }
+        // Settle on complete data when Torn allows; a report re-syncs otherwise.
+        let ended = Utc::now().timestamp() as u64;
+        match ctx.data().revive_monitor.sync_for_contract(ended).await {
+            Ok(_) => contract.revives_synced = true,
+            Err(e) => log::warn!(
+                "Ending contract {} without complete revive data: {e:#}",
+                contract.contract_id
+            ),
         }
 
-        if backfill_count > 0 {
-            log::info!("Backfilled money for {} revives across {} old contracts", backfill_count, unsettled.len());
-        }
+        let reviving_faction_ids = ctx.data().secrets.reviving_faction_ids();
+        end_contract(
+            &mut contract,
+            ended,
+            &reviving_faction_ids,
+            &ctx.data().torn_api,
+            Some(ctx.author().id.get()),
+        )
+        .await?;
 
-        match &settled {
-            Ok(n) => log::info!("Settled money for {n} revives under contract {}", contract.contract_id),
-            Err(e) => log::error!("Money settlement failed for contract {}: {e:#}", contract.contract_id),
-        }
+        announcer(&ctx).ended(&contract).await;
 
         message = MessageBuilder::new()
             .push(format!(
@@ -310,6 +423,233 @@ pub async fn end(
     Ok(())
 }
 
+/// Change the terms of a contract
+#[poise::command(slash_command)]
+#[allow(clippy::too_many_arguments)] // one per slash command option
+pub async fn edit(
+    ctx: Context<'_>,
+    #[description = "ID of the contract to edit"] contract_id: String,
+    #[description = "New name"] contract_name: Option<String>,
+    #[description = "New minimum chance of success to count for payment"] min_chance: Option<u64>,
+    #[description = "New pricing tier"] pricing_type: Option<PricingChoice>,
+    #[description = "New cut the faction gets from the contract"] faction_cut: Option<u64>,
+    #[description = "New start time in UTC as YYYY-MM-DD HH:MM"] start_time: Option<String>,
+    #[description = "New budget as the final price in $ (0 removes it)"] budget: Option<u64>,
+    #[description = "End the contract automatically once the budget is reached"] end_at_budget: Option<bool>,
+) -> Result<(), Error> {
+    if !ensure_admin(&ctx).await? {
+        return Ok(());
+    }
+
+    let Some(mut contract) = find_contract(&ctx, &contract_id).await? else {
+        return Ok(());
+    };
+
+    let started = match start_time {
+        Some(start_time) => match parse_contract_time(&start_time) {
+            Ok(started) => Some(started.timestamp() as u64),
+            Err(error) => {
+                ctx.send(CreateReply::default().content(error).ephemeral(true))
+                    .await?;
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+
+    let changes = ContractChanges {
+        contract_name,
+        min_chance,
+        pricing_type: pricing_type.map(PricingType::from),
+        faction_cut: faction_cut.map(|cut| cut as i64),
+        started,
+        budget,
+        end_at_budget,
+    };
+    if let Err(error) = check_changes(&contract, &changes) {
+        ctx.send(CreateReply::default().content(error).ephemeral(true))
+            .await?;
+        return Ok(());
+    }
+
+    // Settling an ended contract again can take longer than an interaction allows.
+    ctx.defer_ephemeral().await?;
+
+    let before = contract.clone();
+    let edits = edit_contract(&mut contract, changes, ctx.author().id.get()).await?;
+
+    let message = if edits.is_empty() {
+        "Nothing changed.".to_string()
+    } else {
+        let mut message = format!(
+            "Contract {} ({}) updated:\n{}",
+            contract.contract_name,
+            contract.contract_id,
+            describe_edits(&edits)
+        );
+        let reviving_faction_ids = ctx.data().secrets.reviving_faction_ids();
+        if let Some(settled) = resettle_if_ended(
+            &mut contract,
+            &before,
+            &reviving_faction_ids,
+            &ctx.data().torn_api,
+            ctx.author().id.get(),
+        )
+        .await
+        {
+            message.push('\n');
+            message.push_str(&settled);
+        }
+        message
+    };
+
+    ctx.send(CreateReply::default().content(message).ephemeral(true))
+        .await?;
+
+    Ok(())
+}
+
+/// Pause coverage of an active contract; revives don't count until it is resumed
+#[poise::command(slash_command)]
+pub async fn pause(
+    ctx: Context<'_>,
+    #[description = "ID of the contract to pause"] contract_id: String,
+) -> Result<(), Error> {
+    if !ensure_admin(&ctx).await? {
+        return Ok(());
+    }
+
+    let Some(mut contract) = find_contract(&ctx, &contract_id).await? else {
+        return Ok(());
+    };
+
+    let refusal = if contract.status != Status::Active {
+        Some("Only an active contract can be paused.")
+    } else if contract.is_paused() {
+        Some("This contract is already paused.")
+    } else {
+        None
+    };
+    if let Some(refusal) = refusal {
+        ctx.send(CreateReply::default().content(refusal).ephemeral(true))
+            .await?;
+        return Ok(());
+    }
+
+    let now = Utc::now().timestamp() as u64;
+    contract.pauses.push(Pause { from: now, to: None });
+    Database::update(contract.clone(), doc! {"contract_id": contract.contract_id.clone()})
+        .await?;
+    record_event(
+        &contract.contract_id,
+        ContractEventKind::Paused,
+        Some(ctx.author().id.get()),
+        doc! { "from": now as i64 },
+    )
+    .await;
+    log::info!("Contract {} paused", contract.contract_id);
+
+    ctx.send(
+        CreateReply::default()
+            .content(format!(
+                "Contract {} ({}) paused at {}. Revives don't count until `/contract resume`.",
+                contract.contract_name,
+                contract.contract_id,
+                format_time(now)
+            ))
+            .ephemeral(true),
+    )
+    .await?;
+
+    announcer(&ctx).paused(&contract).await;
+
+    Ok(())
+}
+
+/// Resume coverage of a paused contract
+#[poise::command(slash_command)]
+pub async fn resume(
+    ctx: Context<'_>,
+    #[description = "ID of the contract to resume"] contract_id: String,
+) -> Result<(), Error> {
+    if !ensure_admin(&ctx).await? {
+        return Ok(());
+    }
+
+    let Some(mut contract) = find_contract(&ctx, &contract_id).await? else {
+        return Ok(());
+    };
+
+    let now = Utc::now().timestamp() as u64;
+    let Some(pause) = contract
+        .pauses
+        .last_mut()
+        .filter(|pause| pause.to.is_none() && contract.status == Status::Active)
+    else {
+        ctx.send(
+            CreateReply::default()
+                .content("This contract is not paused.")
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(());
+    };
+    pause.to = Some(now);
+    let from = pause.from;
+
+    Database::update(contract.clone(), doc! {"contract_id": contract.contract_id.clone()})
+        .await?;
+    record_event(
+        &contract.contract_id,
+        ContractEventKind::Resumed,
+        Some(ctx.author().id.get()),
+        doc! { "from": from as i64, "to": now as i64 },
+    )
+    .await;
+    log::info!("Contract {} resumed", contract.contract_id);
+
+    ctx.send(
+        CreateReply::default()
+            .content(format!(
+                "Contract {} ({}) resumed at {}; it was paused since {}.",
+                contract.contract_name,
+                contract.contract_id,
+                format_time(now),
+                format_time(from)
+            ))
+            .ephemeral(true),
+    )
+    .await?;
+
+    announcer(&ctx).resumed(&contract).await;
+
+    Ok(())
+}
+
+/// Looks up a contract by ID, replying when there is none.
+async fn find_contract(ctx: &Context<'_>, contract_id: &str) -> Result<Option<Contract>, Error> {
+    let contract: Option<Contract> =
+        Database::get_collection_with_filter(Some(doc! {"contract_id": contract_id}))
+            .await?
+            .pop();
+
+    if contract.is_none() {
+        ctx.send(
+            CreateReply::default()
+                .content(
+                    MessageBuilder::new()
+                        .push("No contract found with ID: ")
+                        .push_mono(contract_id)
+                        .build(),
+                )
+                .ephemeral(true),
+        )
+        .await?;
+    }
+
+    Ok(contract)
+}
+
 /// List contracts
 #[poise::command(slash_command)]
 pub async fn list(
@@ -329,7 +669,8 @@ pub async fn list(
         StatusFilter::All => None,
     };
 
-    let (content, embed, components) = create_page(1, PAGE_SIZE, filter.clone()).await;
+    let (content, embed, components) =
+        create_page(&announcer(&ctx), 1, PAGE_SIZE, filter.clone()).await;
 
     let handle = ctx
         .send(
@@ -354,6 +695,206 @@ pub async fn list(
     Ok(())
 }
 
+/// Link a client faction to the Discord channel where it is told about its contracts
+#[poise::command(slash_command)]
+pub async fn link(
+    ctx: Context<'_>,
+    #[description = "The client faction"] faction_id: u64,
+    #[description = "ID of the client's channel (Copy Channel ID); leave empty to unlink"]
+    channel_id: Option<String>,
+) -> Result<(), Error> {
+    if !ensure_admin(&ctx).await? {
+        return Ok(());
+    }
+
+    let Some(channel_id) = channel_id else {
+        let removed = Database::delete_doc::<FactionChannel>(doc! {"faction_id": faction_id as i64}).await?;
+        let content = if removed > 0 {
+            format!("Faction {faction_id} will no longer be told about its contracts.")
+        } else {
+            format!("Faction {faction_id} had no linked channel.")
+        };
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
+        return Ok(());
+    };
+
+    let channel = match channel_id.trim().parse::<u64>() {
+        Ok(id) if id > 0 => ChannelId::new(id),
+        _ => {
+            ctx.send(
+                CreateReply::default()
+                    .content("Invalid channel ID.")
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    // The bot has to be in the client's server to post there.
+    if let Err(e) = channel.to_channel(ctx).await {
+        log::info!("Cannot link channel {channel}: {e}");
+        ctx.send(
+            CreateReply::default()
+                .content("Deathfr cannot see that channel. Make sure it is in the client's server and can read the channel.")
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    Database::delete_doc::<FactionChannel>(doc! {"faction_id": faction_id as i64}).await?;
+    Database::insert(FactionChannel {
+        faction_id,
+        channel_id: channel.get(),
+    })
+    .await?;
+
+    ctx.send(
+        CreateReply::default()
+            .content(format!(
+                "Faction {faction_id} will be told about its contracts in <#{channel}>."
+            ))
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Show everything recorded about a contract, oldest first
+#[poise::command(slash_command)]
+pub async fn history(
+    ctx: Context<'_>,
+    #[description = "ID of the contract"] contract_id: String,
+) -> Result<(), Error> {
+    if !ensure_admin(&ctx).await? {
+        return Ok(());
+    }
+
+    let options = mongodb::options::FindOptions::builder()
+        .sort(doc! {"timestamp": 1, "_id": 1})
+        .build();
+    let mut events: Vec<ContractEvent> = Database::get_collection_with_filter_and_options(
+        Some(doc! {"contract_id": contract_id.clone()}),
+        Some(options),
+    )
+    .await?;
+
+    let options = mongodb::options::FindOptions::builder()
+        .sort(doc! {"edited_at": 1, "_id": 1})
+        .build();
+    let edits: Vec<ContractEdit> = Database::get_collection_with_filter_and_options(
+        Some(doc! {"contract_id": contract_id.clone()}),
+        Some(options),
+    )
+    .await?;
+    events.extend(edit_events(&edits));
+    // Stable, so events at the same second keep their stored order.
+    events.sort_by_key(|event| event.timestamp);
+
+    if events.is_empty() {
+        ctx.send(
+            CreateReply::default()
+                .content(
+                    MessageBuilder::new()
+                        .push("No history recorded for contract ")
+                        .push_mono(contract_id)
+                        .build(),
+                )
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let lines: Vec<String> = events
+        .iter()
+        .map(|event| {
+            let actor = match event.actor {
+                Some(actor) => format!("<@{actor}>"),
+                None => "Deathfr".to_string(),
+            };
+            let mut line = format!(
+                "<t:{}:f> **{}** by {actor}",
+                event.timestamp,
+                event.kind.label()
+            );
+            if !event.payload.is_empty() {
+                line.push_str(" — ");
+                line.push_str(&describe_payload(&event.payload));
+            }
+            line
+        })
+        .collect();
+
+    // Newest events win when the history doesn't fit in one embed.
+    let mut shown = Vec::new();
+    let mut length = 0;
+    for line in lines.iter().rev() {
+        if length + line.len() + 1 > HISTORY_LIMIT {
+            break;
+        }
+        length += line.len() + 1;
+        shown.push(line.as_str());
+    }
+    shown.reverse();
+    let omitted = lines.len() - shown.len();
+
+    let mut reply = CreateReply::default()
+        .embed(
+            CreateEmbed::new()
+                .title(format!("History of contract {contract_id}"))
+                .description(shown.join("\n")),
+        )
+        .ephemeral(true);
+    if omitted > 0 {
+        reply = reply.content(format!("{omitted} older event(s) don't fit and are left out."));
+    }
+    ctx.send(reply).await?;
+
+    Ok(())
+}
+
+/// Stays under Discord's 4096 character limit for an embed description.
+const HISTORY_LIMIT: usize = 4000;
+
+/// Payload keys holding unix times, shown as Discord timestamps.
+const TIME_KEYS: [&str; 5] = ["started", "ended", "end_time", "from", "to"];
+
+/// "key: value" pairs of an event payload for `/contract history`.
+fn describe_payload(payload: &Document) -> String {
+    payload
+        .iter()
+        .map(|(key, value)| {
+            let value = match value {
+                Bson::Int64(time) if TIME_KEYS.contains(&key.as_str()) => format!("<t:{time}:f>"),
+                Bson::String(text) => text.clone(),
+                Bson::Document(change) => {
+                    let show = |value: &str| match value.parse::<u64>() {
+                        Ok(time) if TIME_KEYS.contains(&key.as_str()) => format!("<t:{time}:f>"),
+                        _ => value.to_string(),
+                    };
+                    format!(
+                        "{} → {}",
+                        show(change.get_str("old").unwrap_or("?")),
+                        show(change.get_str("new").unwrap_or("?"))
+                    )
+                }
+                Bson::Array(items) => items
+                    .iter()
+                    .map(|item| item.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                other => other.to_string(),
+            };
+            format!("{key}: {value}")
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 /// Handles the next/previous pagination buttons on `/contract list` messages
 pub async fn handle_pagination(
     ctx: &serenity::all::Context,
@@ -386,7 +927,12 @@ pub async fn handle_pagination(
         (info.page, info.filter.clone())
     };
 
-    let (content, embed, components) = create_page(page, PAGE_SIZE, filter).await;
+    let announcer = Announcer {
+        http: &ctx.http,
+        secrets: &data.secrets,
+        api: &data.torn_api,
+    };
+    let (content, embed, components) = create_page(&announcer, page, PAGE_SIZE, filter).await;
 
     component
         .create_response(
@@ -404,11 +950,19 @@ pub async fn handle_pagination(
 }
 
 async fn create_page(
+    announcer: &Announcer<'_>,
     page: u64,
     page_size: u64,
     filter: Option<Document>,
 ) -> (String, CreateEmbed, Vec<CreateActionRow>) {
-    promote_pending_contracts().await;
+    match promote_pending_contracts().await {
+        Ok(promoted) => {
+            for contract in &promoted {
+                announcer.started(contract).await;
+            }
+        }
+        Err(e) => log::error!("Failed to promote pending contracts: {e:#}"),
+    }
 
     let size = Database::get_collection_size(filter.clone()).await.unwrap();
 
@@ -442,6 +996,7 @@ async fn create_page(
             contract.contract_name.clone()
         };
         let status = match contract.status {
+            Status::Active if contract.is_paused() => "Paused",
             Status::Active => "Active",
             Status::Pending => "Pending",
             Status::Ended => "Ended",
@@ -493,33 +1048,25 @@ async fn create_page(
     ("List of contracts".to_string(), embed, components)
 }
 
-async fn promote_pending_contracts() {
-    let pending_contracts = Database::get_collection_with_filter::<crate::database::structures::Contract>(Some(
-        doc! {"status": bson::to_bson(&Status::Pending).unwrap()}
-    ))
-    .await
-    .unwrap();
-
-    let now = Utc::now().timestamp() as u64;
-
-    for mut contract in pending_contracts {
-        if contract.started <= now {
-            contract.status = Status::Active;
-            Database::update(contract.clone(), doc! {"contract_id": contract.contract_id.clone()})
-                .await
-                .unwrap();
-        }
-    }
-}
-
-fn parse_contract_start_time(start_time: &str) -> Result<DateTime<Utc>, String> {
-    let parsed = NaiveDateTime::parse_from_str(start_time, "%Y-%m-%d %H:%M").map_err(|_| {
-        "Invalid start time format. Use YYYY-MM-DD HH:MM in UTC.".to_string()
+fn parse_contract_time(time: &str) -> Result<DateTime<Utc>, String> {
+    let parsed = NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M").map_err(|_| {
+        "Invalid time format. Use YYYY-MM-DD HH:MM in UTC.".to_string()
     })?;
 
     Ok(DateTime::<Utc>::from_naive_utc_and_offset(parsed, Utc))
 }
 
+fn parse_faction_ids(ids: &str) -> Result<Vec<u64>, String> {
+    ids.split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| {
+            id.parse::<u64>()
+                .map_err(|_| format!("`{id}` is not a faction ID."))
+        })
+        .collect()
+}
+
 fn format_time(time: u64) -> String {
     format!("<t:{}:f>", time)
 }
@@ -1,8 +1,15 @@
+use crate::bot::announcements::Announcer;
 use crate::bot::auth::{level_of, AccessLevel};
 use crate::bot::data::{Context, Data, Error};
-use crate::database::structures::{Contract, Status};
+use crate::bot::tools::contract_edit::{
+    check_changes, describe_edits, edit_contract, resettle_if_ended, ContractChanges,
+};
+use crate::bot::tools::contract_events::{contract_terms, record_event};
+use crate::bot::tools::get_faction_cache::get_faction_cache;
+use crate::database::structures::{Contract, ContractEventKind, Status};
 use crate::database::Database;
-use crate::pricing::PricingType;
+use crate::pricing::{ContractScope, PricingType};
+use crate::torn_api::{Priority, TornError};
 use chrono::{DateTime, NaiveDateTime, Utc};
 use mongodb::bson::doc;
 use poise::CreateReply;
@@ -11,13 +18,12 @@ use rand::Rng;
 use serenity::all::{
     ActionRowComponent, ButtonStyle, ComponentInteraction, CreateActionRow, CreateButton,
     CreateEmbed, CreateInputText, CreateInteractionResponse, CreateInteractionResponseFollowup,
-    CreateInteractionResponseMessage, CreateModal, InputTextStyle,
+    CreateInteractionResponseMessage, CreateModal, EditInteractionResponse, InputTextStyle,
     ModalInteraction, UserId,
 };
 use serenity::builder::CreateActionRow as CreateActionRowBuilder;
 use serenity::utils::MessageBuilder;
-use torn_api::models::FactionId;
-use torn_api::{ApiError, Error as TornError};
+use torn_api::ApiError;
 
 const MODAL_ID: &str = "contract_wizard_modal";
 const INPUT_ID: &str = "contract_wizard_input";
@@ -42,6 +48,8 @@ pub struct ContractWizardState {
     pub scheduled_start: Option<DateTime<Utc>>,
     /// User completed step 6 via skip or schedule (used for Keep current after back).
     pub start_time_set: bool,
+    /// Contract being edited; `None` while creating a new one.
+    pub editing: Option<String>,
     pub error: Option<String>,
 }
 
@@ -153,9 +161,67 @@ pub async fn start_contract_interactive(ctx: Context<'_>) -> Result<(), Error> {
         faction_cut_set: false,
         scheduled_start: None,
         start_time_set: false,
+        editing: None,
+        error: None,
+    };
+
+    show_wizard(ctx, state).await
+}
+
+/// Step-by-step editing of an existing contract's terms, prefilled with its current values.
+#[poise::command(slash_command, rename = "edit-contract-interactive")]
+pub async fn edit_contract_interactive(
+    ctx: Context<'_>,
+    #[description = "ID of the contract to edit"] contract_id: String,
+) -> Result<(), Error> {
+    if !ensure_admin(&ctx).await? {
+        return Ok(());
+    }
+
+    let contract: Option<Contract> =
+        Database::get_collection_with_filter(Some(doc! {"contract_id": contract_id.clone()}))
+            .await?
+            .pop();
+    let Some(contract) = contract else {
+        ctx.send(
+            CreateReply::default()
+                .content(
+                    MessageBuilder::new()
+                        .push("No contract found with ID: ")
+                        .push_mono(contract_id)
+                        .build(),
+                )
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(());
+    };
+
+    let faction_name = get_faction_cache(contract.faction_id, &ctx.data().torn_api, Priority::Interactive)
+        .await
+        .ok()
+        .map(|faction| faction.name);
+
+    let state = ContractWizardState {
+        user_id: ctx.author().id,
+        step: WizardStep::ContractName,
+        contract_name: Some(contract.contract_name),
+        faction_id: Some(contract.faction_id),
+        faction_name,
+        min_chance: Some(contract.min_chance),
+        pricing_type: Some(contract.pricing_type),
+        faction_cut: Some(contract.faction_cut.max(0) as u64),
+        faction_cut_set: true,
+        scheduled_start: DateTime::<Utc>::from_timestamp(contract.started as i64, 0),
+        start_time_set: true,
+        editing: Some(contract.contract_id),
         error: None,
     };
 
+    show_wizard(ctx, state).await
+}
+
+async fn show_wizard(ctx: Context<'_>, state: ContractWizardState) -> Result<(), Error> {
     let (content, embed, components) = render_step(&state);
 
     let handle = ctx
@@ -217,6 +283,11 @@ pub async fn handle_component(
 
         match custom_id {
             "contract_wizard_cancel" => {
+                let cancelled = if state.editing.is_some() {
+                    "Contract edit cancelled."
+                } else {
+                    "Contract creation cancelled."
+                };
                 wizards.remove(&component.message.id);
                 drop(wizards);
                 component
@@ -224,7 +295,7 @@ pub async fn handle_component(
                         &ctx.http,
                         CreateInteractionResponse::UpdateMessage(
                             CreateInteractionResponseMessage::new()
-                                .content("Contract creation cancelled.")
+                                .content(cancelled)
                                 .components(vec![]),
                         ),
                     )
@@ -253,6 +324,11 @@ pub async fn handle_component(
                     state.error = None;
                     state.clone()
                 }
+                WizardStep::StartTime if state.editing.is_some() => {
+                    // Keeps "start immediately" from silently moving an existing contract.
+                    component.defer(&ctx.http).await?;
+                    return Ok(());
+                }
                 WizardStep::StartTime => {
                     state.scheduled_start = None;
                     state.start_time_set = true;
@@ -295,6 +371,13 @@ pub async fn handle_component(
                 state.error = None;
                 state.clone()
             }
+            "contract_wizard_open_modal" | "contract_wizard_change"
+                if state.editing.is_some() && state.step == WizardStep::FactionId =>
+            {
+                // The faction of an existing contract can't be changed.
+                component.defer(&ctx.http).await?;
+                return Ok(());
+            }
             "contract_wizard_open_modal" | "contract_wizard_change" => {
                 drop(wizards);
                 return open_modal_for_step(ctx, data, component, custom_id).await;
@@ -308,6 +391,9 @@ pub async fn handle_component(
             "contract_wizard_confirm" => {
                 let snapshot = state.clone();
                 drop(wizards);
+                if snapshot.editing.is_some() {
+                    return confirm_and_edit(ctx, data, component, snapshot).await;
+                }
                 return confirm_and_create(ctx, data, component, snapshot).await;
             }
             _ => {
@@ -401,14 +487,10 @@ pub async fn handle_modal(
                 return finish_modal(ctx, data, modal, message_id, next_state).await;
             };
 
-            match data
-                .torn_api
-                .get_faction_basic(FactionId::new(faction_id as i32))
-                .await
-            {
-                Ok(faction_data) => {
+            match get_faction_cache(faction_id, &data.torn_api, Priority::Interactive).await {
+                Ok(faction) => {
                     next_state.faction_id = Some(faction_id);
-                    next_state.faction_name = Some(faction_data.basic.name);
+                    next_state.faction_name = Some(faction.name);
                     next_state.step = WizardStep::MinChance;
                 }
                 Err(TornError::Api(ApiError::IncorrectId | ApiError::IncorrectIdEntityRelation)) => {
@@ -418,6 +500,12 @@ pub async fn handle_modal(
                         "Invalid faction ID — check the number and try again.".to_string(),
                     );
                 }
+                Err(TornError::Unavailable(unavailable)) => {
+                    next_state.faction_id = None;
+                    next_state.faction_name = None;
+                    next_state.error = Some(unavailable.to_string());
+                    return finish_modal(ctx, data, modal, message_id, next_state).await;
+                }
                 Err(e) => {
                     let message = format!("Failed to fetch faction data from Torn: {e:#}");
                     log::info!("{message}");
@@ -515,11 +603,7 @@ async fn confirm_and_create(
     };
 
     let faction_id = state.faction_id.unwrap_or(0);
-    match data
-        .torn_api
-        .get_faction_basic(FactionId::new(faction_id as i32))
-        .await
-    {
+    match get_faction_cache(faction_id, &data.torn_api, Priority::Interactive).await {
         Ok(_) => {}
         Err(TornError::Api(ApiError::IncorrectId | ApiError::IncorrectIdEntityRelation)) => {
             let mut errored = state.clone();
@@ -535,6 +619,17 @@ async fn confirm_and_create(
             }
             return respond_update(ctx, component, &errored).await;
         }
+        Err(TornError::Unavailable(unavailable)) => {
+            let mut errored = state.clone();
+            errored.error = Some(unavailable.to_string());
+            {
+                let mut wizards = data.contract_wizards.lock().await;
+                if let Some(wizard) = wizards.get_mut(&component.message.id) {
+                    *wizard = errored.clone();
+                }
+            }
+            return respond_update(ctx, component, &errored).await;
+        }
         Err(e) => {
             let message = format!("Failed to fetch faction data from Torn: {e:#}");
             log::info!("{message}");
@@ -558,7 +653,9 @@ async fn confirm_and_create(
         contract_id: generate_contract_id().await,
         contract_name: state.contract_name.clone().unwrap_or_default(),
         faction_id,
+        faction_ids: vec![faction_id],
         min_chance: state.min_chance.unwrap_or(0),
+        scope: ContractScope::All,
         started: started_at.timestamp() as u64,
         ended: 0,
         status,
@@ -566,6 +663,11 @@ async fn confirm_and_create(
         pricing_type,
         revives_synced: false,
         money_settled: false,
+        budget: None,
+        end_at_budget: false,
+        budget_alerted: 0,
+        pauses: Vec::new(),
+        end_time: None,
     };
 
     let status_label = match contract.status {
@@ -588,7 +690,14 @@ async fn confirm_and_create(
         .push(").")
         .build();
 
-    Database::insert(contract).await.unwrap();
+    Database::insert(contract.clone()).await.unwrap();
+    record_event(
+        &contract_id,
+        ContractEventKind::WizardConfirmed,
+        Some(component.user.id.get()),
+        contract_terms(&contract),
+    )
+    .await;
 
     data.contract_wizards.lock().await.remove(&component.message.id);
 
@@ -618,6 +727,117 @@ async fn confirm_and_create(
         )
         .await?;
 
+    Announcer {
+        http: &ctx.http,
+        secrets: &data.secrets,
+        api: &data.torn_api,
+    }
+    .created(&contract)
+    .await;
+
+    Ok(())
+}
+
+async fn confirm_and_edit(
+    ctx: &serenity::all::Context,
+    data: &Data,
+    component: &ComponentInteraction,
+    state: ContractWizardState,
+) -> Result<(), Error> {
+    let Some(contract_id) = state.editing.clone() else {
+        component.defer(&ctx.http).await?;
+        return Ok(());
+    };
+
+    let contract: Option<Contract> =
+        Database::get_collection_with_filter(Some(doc! {"contract_id": contract_id.clone()}))
+            .await?
+            .pop();
+    let Some(mut contract) = contract else {
+        data.contract_wizards.lock().await.remove(&component.message.id);
+        component
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .content(format!("Contract `{contract_id}` no longer exists."))
+                        .embeds(vec![])
+                        .components(vec![]),
+                ),
+            )
+            .await?;
+        return Ok(());
+    };
+
+    let pricing_type = state.pricing_type.unwrap_or(contract.pricing_type);
+    let changes = ContractChanges {
+        contract_name: state.contract_name.clone(),
+        min_chance: state.min_chance,
+        pricing_type: Some(pricing_type),
+        faction_cut: Some(
+            state
+                .faction_cut
+                .map(|cut| cut as i64)
+                .unwrap_or(pricing_type.default_faction_cut()),
+        ),
+        started: state.scheduled_start.map(|started| started.timestamp() as u64),
+        ..Default::default()
+    };
+
+    if let Err(error) = check_changes(&contract, &changes) {
+        let mut errored = state.clone();
+        errored.error = Some(error);
+        {
+            let mut wizards = data.contract_wizards.lock().await;
+            if let Some(wizard) = wizards.get_mut(&component.message.id) {
+                *wizard = errored.clone();
+            }
+        }
+        return respond_update(ctx, component, &errored).await;
+    }
+
+    // Settling an ended contract again can take longer than an interaction allows.
+    component.defer(&ctx.http).await?;
+
+    let before = contract.clone();
+    let edits = edit_contract(&mut contract, changes, component.user.id.get()).await?;
+
+    data.contract_wizards.lock().await.remove(&component.message.id);
+
+    let message = if edits.is_empty() {
+        format!("Nothing changed on contract `{contract_id}`.")
+    } else {
+        let mut message = format!(
+            "Contract {} (`{}`) updated:\n{}",
+            contract.contract_name,
+            contract_id,
+            describe_edits(&edits)
+        );
+        if let Some(settled) = resettle_if_ended(
+            &mut contract,
+            &before,
+            &data.secrets.reviving_faction_ids(),
+            &data.torn_api,
+            component.user.id.get(),
+        )
+        .await
+        {
+            message.push('\n');
+            message.push_str(&settled);
+        }
+        message
+    };
+
+    component
+        .edit_response(
+            &ctx.http,
+            EditInteractionResponse::new()
+                .content(message)
+                .embeds(vec![])
+                .components(vec![]),
+        )
+        .await?;
+
     Ok(())
 }
 
@@ -796,10 +1016,14 @@ fn render_step(state: &ContractWizardState) -> (String, CreateEmbed, Vec<CreateA
         description.push_str(error);
     }
 
+    if state.editing.is_some() && step == WizardStep::FactionId {
+        description.push_str("\n\nThe faction of an existing contract can't be changed.");
+    }
+
     if step == WizardStep::StartTime {
         description.push_str("\n\n");
         description.push_str(START_TIME_FORMAT_HINT);
-        if state.scheduled_start.is_none() {
+        if state.scheduled_start.is_none() && state.editing.is_none() {
             let now = Utc::now().timestamp();
             description.push_str(&format!(
                 "\n\nSkipping starts the contract immediately ({})",
@@ -810,7 +1034,12 @@ fn render_step(state: &ContractWizardState) -> (String, CreateEmbed, Vec<CreateA
 
     let mut embed = CreateEmbed::new()
         .title(format!(
-            "Create Contract (step {}/7) — {}",
+            "{} (step {}/7) — {}",
+            if state.editing.is_some() {
+                "Edit Contract"
+            } else {
+                "Create Contract"
+            },
             step.number(),
             step.title()
         ))
@@ -838,6 +1067,13 @@ fn render_step(state: &ContractWizardState) -> (String, CreateEmbed, Vec<CreateA
                 )]);
             }
         }
+        WizardStep::FactionId if state.editing.is_some() => {
+            rows.push(vec![nav_button(
+                "contract_wizard_keep_current",
+                "Keep current",
+                ButtonStyle::Success,
+            )]);
+        }
         WizardStep::FactionId => {
             if state.faction_id.is_some() && state.error.is_none() {
                 rows.push(vec![
@@ -883,7 +1119,7 @@ fn render_step(state: &ContractWizardState) -> (String, CreateEmbed, Vec<CreateA
             rows.push(row);
         }
         WizardStep::PricingType => {
-            rows.push(vec![
+            let mut row = vec![
                 nav_button(
                     "contract_wizard_pick_external",
                     "External",
@@ -894,7 +1130,18 @@ fn render_step(state: &ContractWizardState) -> (String, CreateEmbed, Vec<CreateA
                     "Inter Alliance",
                     ButtonStyle::Primary,
                 ),
-            ]);
+            ];
+            if let (Some(_), Some(pricing_type)) = (&state.editing, state.pricing_type) {
+                row.insert(
+                    0,
+                    nav_button(
+                        "contract_wizard_keep_current",
+                        format!("Keep ({})", pricing_type.label()),
+                        ButtonStyle::Success,
+                    ),
+                );
+            }
+            rows.push(row);
         }
         WizardStep::FactionCut => {
             let default_cut = state
@@ -926,20 +1173,25 @@ fn render_step(state: &ContractWizardState) -> (String, CreateEmbed, Vec<CreateA
             rows.push(row);
         }
         WizardStep::StartTime => {
-            let mut row = vec![
-                nav_button(
-                    "contract_wizard_skip",
-                    "Skip (starts immediately)",
-                    ButtonStyle::Secondary,
-                ),
-                nav_button(
-                    "contract_wizard_pick_schedule",
-                    "Schedule start",
-                    ButtonStyle::Primary,
-                ),
-            ];
+            let mut row = vec![nav_button(
+                "contract_wizard_pick_schedule",
+                "Schedule start",
+                ButtonStyle::Primary,
+            )];
+            if state.editing.is_none() {
+                row.insert(
+                    0,
+                    nav_button(
+                        "contract_wizard_skip",
+                        "Skip (starts immediately)",
+                        ButtonStyle::Secondary,
+                    ),
+                );
+            }
             if state.start_time_set && state.error.is_none() {
-                let keep_label = if state.scheduled_start.is_some() {
+                let keep_label = if state.editing.is_some() {
+                    "Keep current".to_string()
+                } else if state.scheduled_start.is_some() {
                     "Keep scheduled".to_string()
                 } else {
                     "Keep (starts immediately)".to_string()
@@ -954,7 +1206,11 @@ fn render_step(state: &ContractWizardState) -> (String, CreateEmbed, Vec<CreateA
         WizardStep::Confirm => {
             rows.push(vec![nav_button(
                 "contract_wizard_confirm",
-                "Create contract",
+                if state.editing.is_some() {
+                    "Save changes"
+                } else {
+                    "Create contract"
+                },
                 ButtonStyle::Success,
             )]);
         }
@@ -1001,10 +1257,14 @@ fn render_step(state: &ContractWizardState) -> (String, CreateEmbed, Vec<CreateA
 }
 
 fn step_content(state: &ContractWizardState) -> String {
+    let header = match &state.editing {
+        Some(contract_id) => format!("Interactive contract editing of `{contract_id}`"),
+        None => "Interactive contract creation".to_string(),
+    };
     if let Some(error) = &state.error {
-        format!("Interactive contract creation — **{error}**")
+        format!("{header} — **{error}**")
     } else {
-        "Interactive contract creation".to_string()
+        header
     }
 }
 
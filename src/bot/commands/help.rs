@@ -35,26 +35,76 @@ pub async fn help(ctx: Context<'_>) -> Result<(), Error> {
             "/contract start".to_string(),
             "Creates a new contract and starts it immediately unless `start_time` is provided. Takes `contract_name`, `faction_id`, and `min_chance` as arguments. \n \
                  * `contract_name` is used as a identifier in list so I recommend naming it something meaningful like served faction name + date. \n \
-                 * `faction_id` is the faction you want to track revives for (if **both defense and offensive revives** are provided two different contracts need to be made \n\
+                 * `faction_id` is the faction you want to track revives for \n\
                  * `min_chance` is the minimum revive chance of success to count for payment \n\
                  * `pricing_type` selects the rate tier: `external` ($1M/$750k) or `inter_alliance` ($800k/$550k) \n\
                  * `faction_cut` is the cut the faction gets from the contract (defaults to 10% for external, 0% for inter_alliance) \n\
-                * `start_time` is optional and must use `YYYY-MM-DD HH:MM` in UTC. Future times create a pending contract. \n\
                  Returns contract ID that can be used for ending the contract, and is to be passed to the contracted faction so they can generate report if they want to."
                 .to_string(),
             false,
         ));
+        fields.push((
+            "/contract start (optional)".to_string(),
+            "* `start_time` must use `YYYY-MM-DD HH:MM` in UTC. Future times create a pending contract. \n\
+             * `end_time` uses the same format and ends the contract automatically at that time. \n\
+             * `extra_faction_ids`: comma separated IDs of other factions the contract also covers, billed together. \n\
+             * `scope`: `all` (default), `defensive` (hospitalized by one of `attacker_faction_ids`) or `offensive` (lost their own attack). \n\
+             * `budget`: final price cap in $; admins and the client are alerted at 75% and 100%, and `end_at_budget` ends the contract there."
+                .to_string(),
+            false,
+        ));
         fields.push((
             "/contract end".to_string(),
             "Ends a contract. Takes `contract_id` as argument. Contract ID is returned when creating a new contract."
                 .to_string(),
             false,
         ));
+        fields.push((
+            "/contract edit".to_string(),
+            "Changes the name, `min_chance`, `pricing_type`, `faction_cut`, start time or budget of a pending, active or ended contract; only the options you give change. \
+             `/edit-contract-interactive` does the same step by step. Every change is recorded with who made it, and an ended contract is settled again with the new terms."
+                .to_string(),
+            false,
+        ));
+        fields.push((
+            "/contract pause | resume".to_string(),
+            "Pauses coverage of an active contract, e.g. between war phases, and resumes it later. Revives while paused are left out of the report, the money and `/reviveme`'s contract marker; the contract keeps its ID."
+                .to_string(),
+            false,
+        ));
         fields.push((
             "/contract list".to_string(),
             format!("Lists all contracts. Takes `status` as argument. Status can be `active`, `pending`, `ended`, or `all`. Contracts are separated in to pages by {}", PAGE_SIZE),
             false,
         ));
+        fields.push((
+            "/contract history".to_string(),
+            "Shows everything recorded about a contract: creation, start, edits, end, money settlements, backfill recalculations and reports, with who did it and when."
+                .to_string(),
+            false,
+        ));
+        fields.push((
+            "/contract link".to_string(),
+            "Links a client faction to a channel (by ID) in its Discord server, where Deathfr posts when its contracts start and a summary when they end. Leave `channel_id` empty to unlink."
+                .to_string(),
+            false,
+        ));
+        fields.push((
+            "/revives sync".to_string(),
+            "Fetches new revives from Torn right away instead of waiting for the next scheduled sync, and reports how far behind the revive log is."
+                .to_string(),
+            false,
+        ));
+        if level >= AccessLevel::Owner {
+            fields.push((
+                "/revives backfill".to_string(),
+                "Imports a reviving faction's revives from `start_date` (YYYY-MM-DD, UTC) up to now in the background. \
+                 Progress is shown in a message that keeps updating, the import continues after a restart, \
+                 and ended contracts in the window are recalculated once it finishes."
+                    .to_string(),
+                false,
+            ));
+        }
     }
 
     if level >= AccessLevel::FactionGuild {
@@ -80,6 +130,13 @@ pub async fn help(ctx: Context<'_>) -> Result<(), Error> {
                 .to_string(),
             false,
         ));
+        fields.push((
+            "/mykeys".to_string(),
+            "Manage the keys you donated. `list` shows whether they are still valid and how much they are used, \
+             `limit` sets how many requests per minute a key may make, and `revoke` removes a key right away."
+                .to_string(),
+            false,
+        ));
     }
 
     fields.push((
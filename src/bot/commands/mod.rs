@@ -1,8 +1,10 @@
 pub mod contract;
 pub mod contract_wizard;
 pub mod help;
+pub mod mykeys;
 pub mod new_contract;
 pub mod report;
 pub mod reviveme;
+pub mod revives;
 pub mod stats;
 pub mod submitkey;
@@ -0,0 +1,197 @@
+use crate::bot::data::{Context, Error};
+use crate::database::structures::APIKey;
+use crate::database::Database;
+use mongodb::bson::doc;
+use mongodb::bson::oid::ObjectId;
+use poise::CreateReply;
+use serenity::all::{AutocompleteChoice, CreateEmbed};
+
+/// Manage the Torn API keys you donated
+#[poise::command(slash_command, subcommands("list", "limit", "revoke"))]
+pub async fn mykeys(_ctx: Context<'_>) -> Result<(), Error> {
+    // Parent command of subcommands, never invoked directly.
+    Ok(())
+}
+
+/// Keys submitted by the invoking Discord user.
+async fn donated_keys(ctx: &Context<'_>) -> Vec<APIKey> {
+    Database::get_collection_with_filter(Some(doc! {
+        "discord_id": ctx.author().id.get() as i64
+    }))
+    .await
+    .unwrap_or_default()
+}
+
+async fn autocomplete_key(ctx: Context<'_>, partial: &str) -> Vec<AutocompleteChoice> {
+    donated_keys(&ctx)
+        .await
+        .into_iter()
+        .map(|key| {
+            let status = if key.valid { "valid" } else { "removed" };
+            let name = format!("{} ({status}, {}/min)", key.key_hint, key.rate_limit());
+            (name, key.id.to_hex())
+        })
+        .filter(|(name, _)| name.to_lowercase().contains(&partial.to_lowercase()))
+        .map(|(name, id)| AutocompleteChoice::new(name, id))
+        .collect()
+}
+
+/// Finds one of the invoking user's keys by the id picked in autocomplete; replies when
+/// there is no such key.
+async fn find_donated_key(ctx: &Context<'_>, key: &str) -> Result<Option<APIKey>, Error> {
+    let found = match ObjectId::parse_str(key) {
+        Ok(id) => donated_keys(ctx).await.into_iter().find(|k| k.id == id),
+        Err(_) => None,
+    };
+
+    if found.is_none() {
+        ctx.send(
+            CreateReply::default()
+                .content("No such key among the keys you submitted. Pick one from the list.")
+                .ephemeral(true),
+        )
+        .await?;
+    }
+
+    Ok(found)
+}
+
+/// Show your donated keys, whether they are still valid and how much they are used
+#[poise::command(slash_command)]
+pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
+    let keys = donated_keys(&ctx).await;
+
+    if keys.is_empty() {
+        ctx.send(
+            CreateReply::default()
+                .content("You have not submitted any API keys. Use `/submitkey` to donate one.")
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let api = &ctx.data().torn_api;
+    let fields = keys.iter().map(|key| {
+        let status = if key.valid {
+            "✅ Valid".to_string()
+        } else {
+            format!(
+                "❌ Removed by Torn: {} (code {})",
+                key.error_message.as_deref().unwrap_or("unknown error"),
+                key.error_code.unwrap_or_default()
+            )
+        };
+
+        let usage = match api.key_usage(key.id) {
+            Some(usage) => format!(
+                "{} requests in the last minute, {} in total",
+                usage.last_minute,
+                key.requests + usage.unsaved
+            ),
+            None => format!("Not in use, {} requests in total", key.requests),
+        };
+
+        let access = match &key.access {
+            Some(access) if access.is_known() => format!("Access level {}", access.level),
+            _ => "Access not checked yet".to_string(),
+        };
+
+        (
+            key.key_hint.clone(),
+            format!(
+                "{status}\nLimit: {}/min\nUsage: {usage}\n{access}",
+                key.rate_limit()
+            ),
+            false,
+        )
+    });
+
+    ctx.send(
+        CreateReply::default()
+            .embed(
+                CreateEmbed::new()
+                    .title("Your API keys")
+                    .description("Use `/mykeys limit` to change how many requests per minute a key may make, and `/mykeys revoke` to remove it.")
+                    .fields(fields),
+            )
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Choose how many requests per minute one of your keys may make
+#[poise::command(slash_command)]
+pub async fn limit(
+    ctx: Context<'_>,
+    #[description = "The key to change"]
+    #[autocomplete = "autocomplete_key"]
+    key: String,
+    #[description = "Requests per minute (Torn allows 100 per player across all keys)"]
+    #[min = 1]
+    #[max = 100]
+    requests_per_minute: u32,
+) -> Result<(), Error> {
+    let Some(key) = find_donated_key(&ctx, &key).await? else {
+        return Ok(());
+    };
+
+    Database::update_doc::<APIKey>(
+        doc! { "_id": key.id },
+        doc! { "$set": { "rate_limit": requests_per_minute as i64 } },
+    )
+    .await?;
+
+    ctx.data().torn_api.set_rate_limit(key.id, requests_per_minute);
+
+    log::info!(
+        "User {} set the limit of key {} to {requests_per_minute}/min",
+        ctx.author().id,
+        key.id
+    );
+
+    ctx.send(
+        CreateReply::default()
+            .content(format!(
+                "Key `{}` may now make up to {requests_per_minute} requests per minute.",
+                key.key_hint
+            ))
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Remove one of your keys from Deathfr
+#[poise::command(slash_command)]
+pub async fn revoke(
+    ctx: Context<'_>,
+    #[description = "The key to remove"]
+    #[autocomplete = "autocomplete_key"]
+    key: String,
+) -> Result<(), Error> {
+    let Some(key) = find_donated_key(&ctx, &key).await? else {
+        return Ok(());
+    };
+
+    // Stop using it first, so no request goes out with a key the donor took back.
+    ctx.data().torn_api.remove_key(key.id);
+    Database::delete_doc::<APIKey>(doc! { "_id": key.id }).await?;
+
+    log::info!("User {} revoked key {}", ctx.author().id, key.id);
+
+    ctx.send(
+        CreateReply::default()
+            .content(format!(
+                "Key `{}` has been removed and will no longer be used.",
+                key.key_hint
+            ))
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
@@ -1,16 +1,19 @@
 use crate::bot::auth::{level_of, AccessLevel};
 use crate::bot::data::{Context, Error};
+use crate::bot::tools::contract_events::record_event;
+use crate::bot::tools::contract_scope::resolve_scope;
+use crate::bot::tools::settle_money::contract_revives;
+use crate::bot::tools::get_faction_cache::get_faction_cache;
 use crate::bot::tools::get_player_cache::get_player_cache;
-use crate::database::structures::{Contract, ReviveEntry, Status};
+use crate::database::structures::{Contract, ContractEventKind, ReviveEntry, Status};
 use crate::database::Database;
-use crate::pricing::{classify_revive, format_with_commas, ReviveClass, ReviveCounts};
-use crate::torn_api::TornAPI;
-use mongodb::bson::{doc, Bson};
+use crate::pricing::{classify_revive, format_with_commas, ContractScope, ReviveClass, ReviveCounts};
+use crate::torn_api::{Priority, TornAPI};
+use mongodb::bson::doc;
 use poise::CreateReply;
 use serenity::builder::{CreateEmbed, CreateMessage};
 use std::collections::HashMap;
 use std::sync::Arc;
-use torn_api::models::FactionId;
 
 /// Generate contract report
 #[poise::command(slash_command)]
@@ -55,8 +58,11 @@ pub async fn report(
         Status::Ended => {}
     }
 
+    // Syncing revives and looking up attacker factions can both outlast the
+    // interaction deadline.
+    ctx.defer().await?;
+
     let syncing_status = if !contract.revives_synced {
-        ctx.defer().await?;
         let status = ctx
             .send(
                 CreateReply::default().content("Generating report — syncing revive data…"),
@@ -75,8 +81,8 @@ pub async fn report(
         {
             Ok(result) => {
                 log::info!(
-                    "Report for contract {}: revive sync complete — inserted={}, has_backlog={}",
-                    contract_id, result.total_inserted, result.has_backlog
+                    "Report for contract {}: revive sync complete — inserted={}, behind={}s",
+                    contract_id, result.total_inserted, result.behind().as_secs()
                 );
             }
             Err(e) => {
@@ -106,27 +112,38 @@ pub async fn report(
     };
 
     let reviving_faction_ids = secrets.reviving_faction_ids();
-    let reviver_faction_filter: Vec<Bson> = reviving_faction_ids
-        .iter()
-        .map(|id| Bson::Int64(*id as i64))
-        .collect();
+    let target_faction_ids = contract.target_factions();
 
     log::info!("Report for contract {}: querying revives from database", contract_id);
-    let revives = Database::get_collection_with_filter::<ReviveEntry>(Some(doc! {
-        "timestamp": {
-            "$gte": Bson::Int64(contract.started as i64),
-            "$lte": Bson::Int64(contract.ended as i64)
-        },
-        "target_faction": Bson::Int64(contract.faction_id as i64),
-        "reviver_faction": { "$in": reviver_faction_filter }
-    }))
-    .await
-    .unwrap();
+    let revives = contract_revives(&contract, &reviving_faction_ids).await?;
     log::info!("Report for contract {}: fetched {} revives from database", contract_id, revives.len());
 
+    let api = ctx.data().torn_api.clone();
+
+    let scope = match resolve_scope(&contract, &revives, &api, Priority::Bulk).await {
+        Ok(scope) => scope,
+        Err(e) => {
+            let message = format!("Failed to fetch attacker factions from Torn: {e:#}");
+            log::error!("Report for contract {}: {message}", contract_id);
+            if let Some(status) = syncing_status {
+                status.delete(ctx).await?;
+            }
+            ctx.send(
+                CreateReply::default()
+                    .content(message)
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
     let mut per_faction_player: HashMap<(u64, u64), Vec<ReviveEntry>> = HashMap::new();
+    // (successful, failed counted, failed ignored, out of scope) per target faction
+    let mut per_target: HashMap<u64, (u64, u64, u64, u64)> = HashMap::new();
     let mut successful = 0;
     let mut failed = 0;
+    let mut out_of_scope = 0;
     let len = revives.len();
 
     for revive in revives {
@@ -135,20 +152,26 @@ pub async fn report(
             .or_default()
             .push(revive.clone());
 
-        match classify_revive(&revive, contract.min_chance) {
-            ReviveClass::Success => successful += 1,
-            ReviveClass::FailedCounted => failed += 1,
-            ReviveClass::Ignored => {}
+        let target = per_target.entry(revive.target_faction).or_default();
+        match classify_revive(&revive, contract.min_chance, &scope) {
+            ReviveClass::Success => {
+                successful += 1;
+                target.0 += 1;
+            }
+            ReviveClass::FailedCounted => {
+                failed += 1;
+                target.1 += 1;
+            }
+            ReviveClass::Ignored => target.2 += 1,
+            ReviveClass::OutOfScope => {
+                out_of_scope += 1;
+                target.3 += 1;
+            }
         }
     }
 
-    let api = ctx.data().torn_api.clone();
-
-    log::info!("Report for contract {}: fetching target faction data (faction_id={})", contract_id, contract.faction_id);
-    let faction_data_target = match api
-        .get_faction_basic(FactionId::new(contract.faction_id as i32))
-        .await
-    {
+    log::info!("Report for contract {}: fetching target faction data (factions={:?})", contract_id, target_faction_ids);
+    let (target_names, target_labels) = match fetch_faction_labels(&api, &target_faction_ids).await {
         Ok(data) => data,
         Err(e) => {
             let message = format!("Failed to fetch faction data from Torn: {e:#}");
@@ -168,7 +191,7 @@ pub async fn report(
 
     log::info!("Report for contract {}: fetching reviver faction data", contract_id);
     let (faction_names, reviver_faction_labels) =
-        match fetch_reviver_factions(&api, &reviving_faction_ids).await {
+        match fetch_faction_labels(&api, &reviving_faction_ids).await {
             Ok(data) => data,
             Err(e) => {
                 let message = format!("Failed to fetch faction data from Torn: {e:#}");
@@ -214,8 +237,8 @@ pub async fn report(
             true,
         )
         .field(
-            "Target Faction",
-            faction_label(&faction_data_target.basic.name, faction_data_target.basic.id.0 as u64),
+            target_field_name(&target_labels),
+            target_labels.join("\n"),
             true,
         )
         .field("", "", false)
@@ -223,9 +246,44 @@ pub async fn report(
         .field("Failed Counted", (failed).to_string(), true)
         .field(
             "Failed Ignored",
-            (len - successful - failed).to_string(),
+            (len - successful - failed - out_of_scope).to_string(),
             true,
-        )
+        );
+
+    if contract.scope != ContractScope::All {
+        embed = embed
+            .field("Scope", contract.scope.label(), true)
+            .field("Out of Scope", out_of_scope.to_string(), true);
+    }
+
+    if target_faction_ids.len() > 1 {
+        let lines: Vec<String> = target_faction_ids
+            .iter()
+            .map(|id| {
+                let (success, failed_counted, failed_ignored, out_of_scope) =
+                    per_target.get(id).copied().unwrap_or_default();
+                format!(
+                    "{}: s: {success}, f: {failed_counted}, fi: {failed_ignored}, os: {out_of_scope}",
+                    target_names.get(id).cloned().unwrap_or_else(|| id.to_string())
+                )
+            })
+            .collect();
+        embed = embed.field("Per Target Faction", lines.join("\n"), false);
+    }
+
+    if !contract.pauses.is_empty() {
+        let pauses: Vec<String> = contract
+            .pauses
+            .iter()
+            .map(|pause| match pause.to {
+                Some(to) => format!("<t:{}:f> – <t:{to}:f>", pause.from),
+                None => format!("<t:{}:f> – end", pause.from),
+            })
+            .collect();
+        embed = embed.field("Paused (not counted)", pauses.join("\n"), false);
+    }
+
+    embed = embed
         .field("Started", format!("<t:{}:f>", contract.started), true)
         .field("Ended", format!("<t:{}:f>", contract.ended), true)
         .field("", "", false)
@@ -249,12 +307,39 @@ pub async fn report(
         );
     };
 
+    if let Some(budget) = contract.budget.filter(|&budget| budget > 0) {
+        embed = embed.field(
+            "Budget",
+            format!(
+                "${} of ${} ({}%)",
+                format_with_commas(breakdown.final_with_markup),
+                format_with_commas(budget),
+                breakdown.final_with_markup.saturating_mul(100) / budget
+            ),
+            true,
+        );
+    }
+
     if let Some(status) = syncing_status {
         status.delete(ctx).await?;
     }
 
     ctx.send(CreateReply::default().embed(embed)).await?;
 
+    record_event(
+        &contract.contract_id,
+        ContractEventKind::ReportGenerated,
+        Some(ctx.author().id.get()),
+        doc! {
+            "successful": successful as i64,
+            "failed_counted": failed as i64,
+            "out_of_scope": out_of_scope as i64,
+            "final_price": breakdown.final_with_markup as i64,
+            "admin": is_admin,
+        },
+    )
+    .await;
+
     // List of rewards is only for admins
     if !is_admin {
         return Ok(());
@@ -272,7 +357,7 @@ pub async fn report(
             "Report for contract {}: fetching player data for {player_id} ({processed}/{player_count})",
             contract_id
         );
-        let player_data = match get_player_cache(*player_id, &api).await {
+        let player_data = match get_player_cache(*player_id, &api, Priority::Bulk).await {
             Some(player) => player,
             None => {
                 log::warn!("Report for contract {}: no player data for {player_id}, skipping", contract_id);
@@ -283,11 +368,13 @@ pub async fn report(
         let mut success = 0u64;
         let mut failed_counted = 0u64;
         let mut failed_ignored = 0u64;
+        let mut out_of_scope = 0u64;
         for entry in entries {
-            match classify_revive(entry, contract.min_chance) {
+            match classify_revive(entry, contract.min_chance, &scope) {
                 ReviveClass::Success => success += 1,
                 ReviveClass::FailedCounted => failed_counted += 1,
                 ReviveClass::Ignored => failed_ignored += 1,
+                ReviveClass::OutOfScope => out_of_scope += 1,
             }
         }
 
@@ -308,13 +395,14 @@ pub async fn report(
             .push((
                 amount,
                 format!(
-                    "* **{} [{}]** - ${} (s: {}, f: {}, fi: {})",
+                    "* **{} [{}]** - ${} (s: {}, f: {}, fi: {}, os: {})",
                     player_data.name,
                     player_id,
                     format_with_commas(amount),
                     success,
                     failed_counted,
-                    failed_ignored
+                    failed_ignored,
+                    out_of_scope
                 ),
             ));
     }
@@ -376,10 +464,7 @@ async fn pending_report(
 ) -> Result<(), Error> {
     let api = ctx.data().torn_api.clone();
 
-    let faction_data_target = match api
-        .get_faction_basic(FactionId::new(contract.faction_id as i32))
-        .await
-    {
+    let (_, target_labels) = match fetch_faction_labels(&api, &contract.target_factions()).await {
         Ok(data) => data,
         Err(e) => {
             ctx.send(
@@ -395,7 +480,7 @@ async fn pending_report(
     let reviving_faction_ids = ctx.data().secrets.reviving_faction_ids();
 
     let (_, reviver_faction_labels) =
-        match fetch_reviver_factions(&api, &reviving_faction_ids).await {
+        match fetch_faction_labels(&api, &reviving_faction_ids).await {
             Ok(data) => data,
             Err(e) => {
                 ctx.send(
@@ -419,39 +504,58 @@ async fn pending_report(
         .description(" ")
         .field(reviver_field_name, reviver_faction_labels.join("\n"), true)
         .field(
-            "Target Faction",
-            faction_label(&faction_data_target.basic.name, faction_data_target.basic.id.0 as u64),
+            target_field_name(&target_labels),
+            target_labels.join("\n"),
             true,
         )
         .field("Contract ID", format!("`{}`", contract.contract_id), true)
         .field("Status", "Pending", true)
         .field("Starts", format!("<t:{}:f>", contract.started), true)
+        .field(
+            "Ends",
+            contract
+                .end_time
+                .map(|end| format!("<t:{end}:f>"))
+                .unwrap_or_else(|| "When ended manually".to_string()),
+            true,
+        )
         .field("Min Chance", format!("{}%", contract.min_chance), true)
+        .field("Scope", contract.scope.label(), true)
         .field("Pricing Type", contract.pricing_type.label(), true);
 
     if is_admin {
         embed = embed.field("Faction Cut", format!("{}%", contract.faction_cut), true);
     }
+    if let Some(budget) = contract.budget {
+        embed = embed.field("Budget", format!("${}", format_with_commas(budget)), true);
+    }
 
     ctx.send(CreateReply::default().embed(embed)).await?;
 
+    record_event(
+        &contract.contract_id,
+        ContractEventKind::ReportGenerated,
+        Some(ctx.author().id.get()),
+        doc! { "pending": true, "admin": is_admin },
+    )
+    .await;
+
     Ok(())
 }
 
-/// Fetches the reviving factions' "Name (ID)" labels, both keyed by faction id and as a list.
-async fn fetch_reviver_factions(
+/// Fetches the factions' "Name (ID)" labels, both keyed by faction id and as a list.
+async fn fetch_faction_labels(
     api: &Arc<TornAPI>,
-    reviving_faction_ids: &[u64],
+    faction_ids: &[u64],
 ) -> Result<(HashMap<u64, String>, Vec<String>), String> {
     let mut faction_names = HashMap::new();
     let mut labels = Vec::new();
 
-    for id in reviving_faction_ids {
-        let faction_data = api
-            .get_faction_basic(FactionId::new(*id as i32))
+    for id in faction_ids {
+        let faction = get_faction_cache(*id, api, Priority::Interactive)
             .await
             .map_err(|e| format!("{e:#}"))?;
-        let label = faction_label(&faction_data.basic.name, faction_data.basic.id.0 as u64);
+        let label = faction_label(&faction.name, faction.faction_id);
         faction_names.insert(*id, label.clone());
         labels.push(label);
     }
@@ -459,6 +563,14 @@ async fn fetch_reviver_factions(
     Ok((faction_names, labels))
 }
 
+fn target_field_name(target_labels: &[String]) -> &'static str {
+    if target_labels.len() == 1 {
+        "Target Faction"
+    } else {
+        "Target Factions"
+    }
+}
+
 /// Formats Torn faction data as "Name (ID)".
 fn faction_label(name: &str, id: u64) -> String {
     format!("{name} ({id})")
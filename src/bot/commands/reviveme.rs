@@ -31,7 +31,19 @@ pub async fn reviveme(ctx: Context<'_>) -> Result<(), Error> {
     }
 
     let verification =
-        resolve_discord_verification(ctx.author().id.get(), data.torn_api.clone()).await;
+        match resolve_discord_verification(ctx.author().id.get(), data.torn_api.clone()).await {
+            Ok(verification) => verification,
+            Err(e) => {
+                log::warn!("Could not verify {:?}: {e:#}", ctx.author().id.get());
+                ctx.send(
+                    CreateReply::default()
+                        .content(e.to_string())
+                        .ephemeral(true),
+                )
+                .await?;
+                return Ok(());
+            }
+        };
 
     let Some(user) = verification else {
         log::warn!("Player {:?} is not verified", ctx.author().id.get());
@@ -45,13 +57,16 @@ pub async fn reviveme(ctx: Context<'_>) -> Result<(), Error> {
     };
 
     let contract: Vec<Contract> = Database::get_collection_with_filter(Some(doc! {
-        "faction_id": user.faction_id as i64,
+        "$or": [
+            { "faction_id": user.faction_id as i64 },
+            { "faction_ids": user.faction_id as i64 },
+        ],
         "status": "active"
     }))
     .await
     .unwrap();
 
-    let is_in_contract = contract.len() > 0;
+    let is_in_contract = contract.iter().any(|contract| !contract.is_paused());
 
     ctx.send(
         CreateReply::default()
@@ -0,0 +1,172 @@
+use crate::bot::auth::{level_of, AccessLevel};
+use crate::bot::commands::contract::ensure_admin;
+use crate::bot::data::{Context, Error};
+use crate::bot::revive_backfill::{progress_text, run_backfill};
+use crate::database::structures::{BackfillStatus, ReviveBackfill};
+use crate::database::Database;
+use chrono::{NaiveDate, Utc};
+use mongodb::bson::{self, doc, oid::ObjectId};
+use poise::CreateReply;
+use serenity::all::CreateMessage;
+
+/// Manage revive data
+#[poise::command(slash_command, subcommands("sync", "backfill"))]
+pub async fn revives(_ctx: Context<'_>) -> Result<(), Error> {
+    // Parent command of subcommands, never invoked directly.
+    Ok(())
+}
+
+/// Fetch new revives from Torn right now instead of waiting for the next poll
+#[poise::command(slash_command)]
+pub async fn sync(ctx: Context<'_>) -> Result<(), Error> {
+    if !ensure_admin(&ctx).await? {
+        return Ok(());
+    }
+
+    ctx.defer_ephemeral().await?;
+    log::info!("User {} forced a revive sync", ctx.author().id);
+
+    let message = match ctx.data().revive_monitor.sync_once().await {
+        Ok(result) if result.caught_up() => format!(
+            "Sync complete: {} revives collected, revive data is up to date.",
+            result.total_inserted
+        ),
+        Ok(result) => format!(
+            "Sync stopped early: {} revives collected, revive data is complete up to <t:{}:f> ({} minutes behind). Run it again or wait for the next poll.",
+            result.total_inserted,
+            result.synced_until,
+            result.behind().as_secs() / 60
+        ),
+        Err(e) => {
+            log::error!("Forced revive sync failed: {e:#}");
+            format!("Revive sync failed: {e:#}")
+        }
+    };
+
+    ctx.send(CreateReply::default().content(message).ephemeral(true))
+        .await?;
+
+    Ok(())
+}
+
+/// Import a faction's older revives from a start date up to now
+#[poise::command(slash_command)]
+pub async fn backfill(
+    ctx: Context<'_>,
+    #[description = "Reviving faction to import revives of"] faction_id: u64,
+    #[description = "First day to import, YYYY-MM-DD (UTC)"] start_date: String,
+) -> Result<(), Error> {
+    if level_of(&ctx) < AccessLevel::Owner {
+        ctx.send(
+            CreateReply::default()
+                .content("Only the bot owner can start a backfill.")
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let reply = |content: String| CreateReply::default().content(content).ephemeral(true);
+
+    let monitor = ctx.data().revive_monitor.clone();
+    if !monitor.covers_faction(faction_id) {
+        ctx.send(reply(format!(
+            "Faction {faction_id} is not a reviving faction. Add it to `REVIVE_FACTIONS` or `REVIVE_SOURCES` first."
+        )))
+        .await?;
+        return Ok(());
+    }
+
+    let Ok(date) = NaiveDate::parse_from_str(start_date.trim(), "%Y-%m-%d") else {
+        ctx.send(reply(
+            "Invalid start date. Use YYYY-MM-DD, e.g. 2025-01-31.".to_string(),
+        ))
+        .await?;
+        return Ok(());
+    };
+    let from = date.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp().max(0) as u64;
+    let to = Utc::now().timestamp() as u64;
+    if from >= to {
+        ctx.send(reply("The start date must be in the past.".to_string()))
+            .await?;
+        return Ok(());
+    }
+
+    let running = Database::get_collection_with_filter::<ReviveBackfill>(Some(doc! {
+        "faction_id": faction_id as i64,
+        "status": bson::to_bson(&BackfillStatus::Running).unwrap(),
+    }))
+    .await?;
+    if !running.is_empty() {
+        ctx.send(reply(format!(
+            "A backfill of faction {faction_id} is already running."
+        )))
+        .await?;
+        return Ok(());
+    }
+
+    ctx.defer_ephemeral().await?;
+
+    let mut job = ReviveBackfill {
+        id: ObjectId::new(),
+        faction_id,
+        from,
+        to,
+        cursor: from,
+        inserted: 0,
+        status: BackfillStatus::Running,
+        error: None,
+        gap_open: false,
+        requested_by: ctx.author().id.get(),
+        channel_id: ctx.channel_id().get(),
+        message_id: 0,
+    };
+
+    // A plain message rather than the interaction reply, so it can still be edited
+    // after the interaction token expires or the bot restarts.
+    let message = ctx
+        .channel_id()
+        .send_message(ctx, CreateMessage::new().content(progress_text(&job, 0)))
+        .await?;
+    job.message_id = message.id.get();
+
+    // The unique index on running jobs turns away a second backfill started meanwhile.
+    if Database::insert_manny(vec![job.clone()]).await?.is_empty() {
+        message.delete(ctx).await?;
+        ctx.send(reply(format!(
+            "A backfill of faction {faction_id} is already running."
+        )))
+        .await?;
+        return Ok(());
+    }
+
+    match monitor.hand_over_to_backfill(faction_id, from, to).await {
+        Ok(gap_open) => job.gap_open = gap_open,
+        Err(e) => {
+            Database::delete_doc::<ReviveBackfill>(doc! { "_id": job.id }).await?;
+            message.delete(ctx).await?;
+            return Err(e);
+        }
+    }
+    if job.gap_open {
+        Database::update(job.clone(), doc! { "_id": job.id }).await?;
+    }
+
+    log::info!(
+        "User {} started a revive backfill of faction {faction_id} from {from}",
+        ctx.author().id
+    );
+    tokio::spawn(run_backfill(
+        ctx.serenity_context().http.clone(),
+        monitor,
+        job,
+        ctx.data().shutdown.clone(),
+    ));
+
+    ctx.send(reply(
+        "Backfill started. Progress is shown in the message above.".to_string(),
+    ))
+    .await?;
+
+    Ok(())
+}
@@ -24,7 +24,18 @@ pub async fn stats(ctx: Context<'_>) -> Result<(), Error> {
     let secrets = &ctx.data().secrets;
     let id = ctx.author().id.get();
 
-    let verification = resolve_discord_verification(id, ctx.data().torn_api.clone()).await;
+    let verification = match resolve_discord_verification(id, ctx.data().torn_api.clone()).await {
+        Ok(verification) => verification,
+        Err(e) => {
+            ctx.send(
+                CreateReply::default()
+                    .content(e.to_string())
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
 
     let Some(mut player) = verification else {
         log::info!("User {} is not verified", id);
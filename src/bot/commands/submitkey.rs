@@ -1,8 +1,9 @@
 use crate::bot::data::{Context, Data, Error};
 use crate::database::structures::APIKey as DbAPIKey;
+use crate::database::encryption::KeyCipher;
 use crate::database::Database;
-use crate::torn_api::APIKey as TornApiKey;
-use mongodb::bson::oid::ObjectId;
+use crate::torn_api::{APIKey as TornApiKey, KeyAccess, TornAPI, TornError};
+use mongodb::bson::{self, doc};
 use poise::CreateReply;
 use serenity::all::{
     ActionRowComponent, ButtonStyle, ComponentInteraction, CreateButton, CreateEmbed,
@@ -10,7 +11,7 @@ use serenity::all::{
     ModalInteraction,
 };
 use serenity::builder::{CreateActionRow, CreateInputText};
-use torn_api::executor::{ExecutorExt, ReqwestClient};
+use torn_api::executor::ExecutorExt;
 
 /// Submit a Torn API key to Deathfr
 #[poise::command(slash_command)]
@@ -29,7 +30,7 @@ pub async fn submitkey(ctx: Context<'_>) -> Result<(), Error> {
             "Deathfr uses Torn API keys **only** to:\n\
             - Authenticate users when using `/reviveme`.\n\
             - Some simple / basic requests to check the API key validity.\n\n\
-            Donated keys are rotated and rate limited to **10 requests per minute**.\n\n\
+            Donated keys are rotated and rate limited to **10 requests per minute** by default; use `/mykeys` to change that or to revoke your key.\n\n\
             Keys are **not** used to access any other information. Revives and other faction relevant information is collected using privately passed keys to me by the faction leader.\n\n\
             If you agree, click **Submit key** below to open the form.",
         );
@@ -116,8 +117,8 @@ pub async fn handle_modal_submit(
         return Ok(());
     };
 
-    let owner_name = match resolve_owner_name(&api_key).await {
-        Some(name) => name,
+    let (owner_id, owner_name, access) = match resolve_owner(&data.torn_api, &api_key).await {
+        Some(owner) => owner,
         None => {
             respond_ephemeral(
                 ctx,
@@ -129,39 +130,108 @@ pub async fn handle_modal_submit(
         }
     };
 
-    let api_key_doc = DbAPIKey {
-        id: ObjectId::new(),
-        api_key: api_key.clone(),
-        name: owner_name.clone(),
-        valid: true,
-    };
+    let existing: Vec<DbAPIKey> =
+        Database::get_collection_with_filter(Some(doc! {
+            "key_hash": KeyCipher::get().fingerprint(&api_key)
+        }))
+            .await
+            .unwrap_or_default();
 
-    if let Err(err) = Database::insert(api_key_doc).await {
-        log::error!("Failed to insert API key into database: {:?}", err);
+    // The donor who submitted a key keeps it: only they see it in `/mykeys` and are told
+    // when Torn rejects it.
+    if let Some(donor) = existing
+        .first()
+        .and_then(|existing| existing.discord_id)
+        .filter(|donor| *donor != modal.user.id.get())
+    {
+        log::warn!(
+            "User {} tried to resubmit an API key donated by {donor}",
+            modal.user.id
+        );
         respond_ephemeral(
             ctx,
             modal,
-            "Failed to save your API key, please try again later.",
+            "This key was already submitted by someone else. Ask them to revoke it with `/mykeys revoke` first.",
         )
         .await;
         return Ok(());
     }
 
+    // Resubmitting a key revives the stored document instead of adding a second one.
+    let (db_id, rate_limit, reply) = if let Some(existing) = existing.first() {
+        let result = Database::update_doc::<DbAPIKey>(
+            doc! { "_id": existing.id },
+            doc! {
+                "$set": {
+                    "name": &owner_name,
+                    "owner_id": owner_id as i64,
+                    "valid": true,
+                    "discord_id": modal.user.id.get() as i64,
+                    "access": bson::to_bson(&access).unwrap_or_default(),
+                },
+                "$unset": { "error_code": "", "error_message": "", "invalidated_at": "" },
+            },
+        )
+        .await;
+
+        if let Err(err) = result {
+            log::error!("Failed to update resubmitted API key: {:?}", err);
+            respond_ephemeral(
+                ctx,
+                modal,
+                "Failed to save your API key, please try again later.",
+            )
+            .await;
+            return Ok(());
+        }
+
+        (
+            existing.id,
+            existing.rate_limit(),
+            "This key was already submitted. It has been checked again and is in use — see `/mykeys`.",
+        )
+    } else {
+        let api_key_doc = DbAPIKey::new(
+            &api_key,
+            owner_name.clone(),
+            owner_id,
+            modal.user.id.get(),
+            access.clone(),
+        );
+        let db_id = api_key_doc.id;
+        let rate_limit = api_key_doc.rate_limit();
+
+        if let Err(err) = Database::insert(api_key_doc).await {
+            log::error!("Failed to insert API key into database: {:?}", err);
+            respond_ephemeral(
+                ctx,
+                modal,
+                "Failed to save your API key, please try again later.",
+            )
+            .await;
+            return Ok(());
+        }
+
+        (
+            db_id,
+            rate_limit,
+            "Your API key has been saved and will be used by Cerberus Alliance. Use `/mykeys` to manage it.",
+        )
+    };
+
     // Add the key to the in-memory TornAPI rotation
     data.torn_api
         .add_key(TornApiKey {
             key: api_key.clone(),
-            rate_limit: 10,
+            rate_limit,
             owner: owner_name.clone(),
+            owner_id: Some(owner_id),
+            db_id: Some(db_id),
+            access,
         })
         .await;
 
-    respond_ephemeral(
-        ctx,
-        modal,
-        "Your API key has been saved and will be used by Cerberus Alliance.",
-    )
-    .await;
+    respond_ephemeral(ctx, modal, reply).await;
 
     Ok(())
 }
@@ -179,17 +249,29 @@ async fn respond_ephemeral(ctx: &serenity::all::Context, modal: &ModalInteractio
         .await;
 }
 
-/// Try to resolve the owner name of a Torn API key by calling Torn API with that key.
+/// Try to resolve the owner (Torn id and name) and access of a Torn API key by calling
+/// Torn API with that key.
 ///
 /// Returns `None` if the key is invalid or the request otherwise fails.
-async fn resolve_owner_name(key: &str) -> Option<String> {
-    let client = ReqwestClient::new(key);
-    match client.user().profile(|b| b).await {
-        Ok(resp) => Some(resp.profile.name),
-        Err(torn_api::Error::Api(_)) => None,
+async fn resolve_owner(api: &TornAPI, key: &str) -> Option<(u64, String, KeyAccess)> {
+    let probe = api.probe(key);
+    let profile = match probe.user().profile(|b| b).await {
+        Ok(resp) => resp.profile,
+        Err(TornError::Api(_)) => return None,
         Err(e) => {
             log::warn!("Failed to validate submitted API key: {e:#}");
-            None
+            return None;
         }
-    }
+    };
+
+    let access = match api.inspect_key(key).await {
+        Ok(access) => access,
+        Err(e) => {
+            // The key works; its access will be learned again on the next start.
+            log::warn!("Failed to inspect submitted API key: {e:#}");
+            KeyAccess::default()
+        }
+    };
+
+    Some((profile.id.0 as u64, profile.name, access))
 }
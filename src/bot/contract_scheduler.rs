@@ -0,0 +1,108 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use mongodb::bson::{self, doc};
+use serenity::all::Http;
+
+use crate::bot::announcements::Announcer;
+use crate::bot::tools::contract_lifecycle::{end_contract, promote_pending_contracts};
+use crate::bot::Secrets;
+use crate::database::structures::{Contract, Status};
+use crate::database::Database;
+use crate::shutdown::Shutdown;
+use crate::torn_api::{ReviveMonitor, TornAPI};
+
+/// How often contracts are checked for their start and end times.
+const TICK: Duration = Duration::from_secs(60);
+
+/// Starts pending contracts and ends contracts that reached their end time, without
+/// waiting for someone to run `/contract list` or `/contract end`.
+pub async fn run_scheduler(
+    http: Arc<Http>,
+    secrets: Secrets,
+    api: Arc<TornAPI>,
+    monitor: Arc<ReviveMonitor>,
+    shutdown: Shutdown,
+) {
+    log::info!("Starting contract scheduler");
+    let announcer = Announcer {
+        http: &http,
+        secrets: &secrets,
+        api: &api,
+    };
+
+    loop {
+        {
+            let Some(_work) = shutdown.begin() else {
+                break;
+            };
+
+            match promote_pending_contracts().await {
+                Ok(promoted) => {
+                    for contract in &promoted {
+                        announcer.started(contract).await;
+                    }
+                }
+                Err(e) => log::error!("Failed to promote pending contracts: {e:#}"),
+            }
+            if let Err(e) = end_due_contracts(&announcer, &monitor).await {
+                log::error!("Failed to end contracts that are due: {e:#}");
+            }
+        }
+
+        if !shutdown.sleep(TICK).await {
+            break;
+        }
+    }
+
+    log::info!("Contract scheduler stopped");
+}
+
+async fn end_due_contracts(
+    announcer: &Announcer<'_>,
+    monitor: &ReviveMonitor,
+) -> anyhow::Result<()> {
+    let now = Utc::now().timestamp();
+    let due: Vec<Contract> = Database::get_collection_with_filter(Some(doc! {
+        "status": { "$ne": bson::to_bson(&Status::Ended).unwrap() },
+        "end_time": { "$ne": null, "$lte": now },
+    }))
+    .await?;
+
+    let reviving_faction_ids = announcer.secrets.reviving_faction_ids();
+
+    for mut contract in due {
+        let Some(end_time) = contract.end_time else {
+            continue;
+        };
+
+        // Settle on complete data when Torn allows; a report re-syncs otherwise.
+        match monitor.sync_for_contract(end_time).await {
+            Ok(_) => contract.revives_synced = true,
+            Err(e) => log::warn!(
+                "Ending contract {} without complete revive data: {e:#}",
+                contract.contract_id
+            ),
+        }
+
+        // One contract that fails to end must not hold up the others due.
+        if let Err(e) =
+            end_contract(&mut contract, end_time, &reviving_faction_ids, announcer.api, None).await
+        {
+            log::error!(
+                "Failed to end contract {} at its scheduled end time: {e:#}",
+                contract.contract_id
+            );
+            continue;
+        }
+        announcer.ended(&contract).await;
+        log::info!(
+            "Contract {} ({}) ended at its scheduled end time",
+            contract.contract_name,
+            contract.contract_id
+        );
+    }
+
+    Ok(())
+}
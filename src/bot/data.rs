@@ -10,12 +10,16 @@ use tokio::sync::Mutex;
 
 use crate::bot::commands::contract::ListMessageInfo;
 use crate::bot::commands::contract_wizard::ContractWizardState;
+use crate::database::encryption::KeyCipher;
+use crate::shutdown::{Shutdown, WorkGuard};
 use crate::torn_api::{ReviveMonitor, ReviveSourceConfig, TornAPI};
 
 /// Everything read from the secrets TOML file at startup.
 pub struct LoadedSecrets {
     pub discord_token: String,
     pub database_url: String,
+    /// Encrypts API keys stored in the database, built from `ENCRYPTION_KEY`.
+    pub cipher: KeyCipher,
     pub secrets: Secrets,
 }
 
@@ -25,6 +29,12 @@ pub struct LoadedSecrets {
 #[derive(Debug, Clone)]
 pub struct Secrets {
     pub revive_channel: u64,
+    /// Channel where revives made under a contract are posted as they are synced.
+    pub revive_log_channel: Option<u64>,
+    /// Channel where revivers are told about contracts starting and ending.
+    pub contract_channel: Option<u64>,
+    /// Channel for operational alerts; the owner is messaged directly when unset.
+    pub ops_channel: Option<u64>,
     pub revive_role: u64,
     pub revive_faction_guilds: Vec<u64>,
     pub revive_faction: u64,
@@ -34,6 +44,8 @@ pub struct Secrets {
     pub revive_sources: Vec<ReviveSourceConfig>,
     pub test_api_key: String,
     pub dev: bool,
+    /// Directory of recorded Torn responses; when set, no request reaches Torn.
+    pub torn_replay_dir: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -41,8 +53,18 @@ pub struct Secrets {
 struct SecretsConfig {
     discord_token: String,
     database_url: String,
+    /// Optional in the file only so a missing key gets upgrade guidance instead of a
+    /// bare parse error.
+    #[serde(default)]
+    encryption_key: Option<String>,
 
     revive_channel: String,
+    #[serde(default)]
+    revive_log_channel: Option<String>,
+    #[serde(default)]
+    ops_channel: Option<String>,
+    #[serde(default)]
+    contract_channel: Option<String>,
     revive_role: String,
     revive_faction_guild_ids: Vec<String>,
     owner_id: String,
@@ -55,10 +77,24 @@ struct SecretsConfig {
     #[serde(default)]
     revive_factions: HashMap<String, String>,
 
+    #[serde(default)]
+    revive_sources: Vec<ReviveSourceEntry>,
+
     test_api_key: String,
 
     // Keep this as string so existing Secrets.*.toml values like `DEV = "true"` still work.
     dev: String,
+
+    #[serde(default)]
+    torn_replay_dir: Option<String>,
+}
+
+/// One `[[REVIVE_SOURCES]]` table: a key and every faction synced with it.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+struct ReviveSourceEntry {
+    api_key: String,
+    faction_ids: Vec<u64>,
 }
 
 impl Secrets {
@@ -69,7 +105,17 @@ impl Secrets {
         let cfg: SecretsConfig = toml::from_str(&secrets_raw)
             .with_context(|| format!("Failed to parse TOML secrets file '{secrets_path}'"))?;
 
-        let revive_sources = parse_revive_factions(&cfg)?;
+        let Some(encryption_key) = cfg.encryption_key.as_deref() else {
+            anyhow::bail!(
+                "ENCRYPTION_KEY is missing from '{secrets_path}'. Stored Torn API keys are now kept \
+                 encrypted: generate a master key with `openssl rand -base64 32`, add it as \
+                 ENCRYPTION_KEY (see Secrets.example.toml) and keep a backup of it. Keys already in \
+                 the database are encrypted on the next start; REVIVE_FACTIONS keys may stay in \
+                 plain text or be sealed with `--seal-key <key>`."
+            );
+        };
+        let cipher = KeyCipher::from_base64(encryption_key)?;
+        let revive_sources = parse_revive_factions(&cfg, &cipher)?;
 
         let (revive_faction, revive_faction_api_key) = if !revive_sources.is_empty() {
             let first = revive_sources.first().unwrap();
@@ -81,17 +127,36 @@ impl Secrets {
             let faction = cfg
                 .revive_faction
                 .as_deref()
-                .context("REVIVE_FACTION is required when REVIVE_FACTIONS is empty")?;
+                .context("REVIVE_FACTION is required when REVIVE_FACTIONS and REVIVE_SOURCES are empty")?;
             let api_key = cfg
                 .revive_faction_api_key
                 .as_deref()
                 .filter(|k| !k.is_empty())
-                .context("REVIVE_FACTION_API_KEY is required when REVIVE_FACTIONS is empty")?;
-            (parse_u64("REVIVE_FACTION", faction)?, api_key.to_string())
+                .context("REVIVE_FACTION_API_KEY is required when REVIVE_FACTIONS and REVIVE_SOURCES are empty")?;
+            let api_key = open_api_key("REVIVE_FACTION_API_KEY", api_key, &cipher)?;
+            (parse_u64("REVIVE_FACTION", faction)?, api_key)
         };
 
         let secrets = Secrets {
             revive_channel: parse_u64("REVIVE_CHANNEL", &cfg.revive_channel)?,
+            revive_log_channel: cfg
+                .revive_log_channel
+                .as_deref()
+                .filter(|c| !c.is_empty())
+                .map(|c| parse_u64("REVIVE_LOG_CHANNEL", c))
+                .transpose()?,
+            contract_channel: cfg
+                .contract_channel
+                .as_deref()
+                .filter(|c| !c.is_empty())
+                .map(|c| parse_u64("CONTRACT_CHANNEL", c))
+                .transpose()?,
+            ops_channel: cfg
+                .ops_channel
+                .as_deref()
+                .filter(|c| !c.is_empty())
+                .map(|c| parse_u64("OPS_CHANNEL", c))
+                .transpose()?,
             revive_role: parse_u64("REVIVE_ROLE", &cfg.revive_role)?,
             revive_faction_guilds: cfg
                 .revive_faction_guild_ids
@@ -104,6 +169,7 @@ impl Secrets {
             revive_sources,
             test_api_key: cfg.test_api_key,
             dev: parse_bool("DEV", &cfg.dev)?,
+            torn_replay_dir: cfg.torn_replay_dir.filter(|d| !d.is_empty()),
             admins: cfg
                 .admins
                 .iter()
@@ -114,6 +180,7 @@ impl Secrets {
         Ok(LoadedSecrets {
             discord_token: cfg.discord_token,
             database_url: cfg.database_url,
+            cipher,
             secrets,
         })
     }
@@ -134,12 +201,11 @@ impl Secrets {
     }
 }
 
-fn parse_revive_factions(cfg: &SecretsConfig) -> anyhow::Result<Vec<ReviveSourceConfig>> {
-    if cfg.revive_factions.is_empty() {
-        return Ok(Vec::new());
-    }
-
-    let mut sources = Vec::with_capacity(cfg.revive_factions.len());
+fn parse_revive_factions(
+    cfg: &SecretsConfig,
+    cipher: &KeyCipher,
+) -> anyhow::Result<Vec<ReviveSourceConfig>> {
+    let mut sources = Vec::with_capacity(cfg.revive_factions.len() + cfg.revive_sources.len());
 
     for (id_str, api_key) in &cfg.revive_factions {
         let field = format!("REVIVE_FACTIONS.{id_str}");
@@ -151,14 +217,38 @@ fn parse_revive_factions(cfg: &SecretsConfig) -> anyhow::Result<Vec<ReviveSource
             .parse::<u64>()
             .with_context(|| format!("Invalid u64 for {field}: '{id_str}'"))?;
         sources.push(ReviveSourceConfig {
-            api_key: api_key.clone(),
+            api_key: open_api_key(&field, api_key, cipher)?,
             faction_ids: vec![faction_id],
         });
     }
 
+    for (i, entry) in cfg.revive_sources.iter().enumerate() {
+        let field = format!("REVIVE_SOURCES[{i}]");
+        if entry.api_key.is_empty() {
+            anyhow::bail!("Invalid {field}: api key must be non-empty");
+        }
+        if entry.faction_ids.is_empty() {
+            anyhow::bail!("Invalid {field}: FACTION_IDS must list at least one faction");
+        }
+        sources.push(ReviveSourceConfig {
+            api_key: open_api_key(&field, &entry.api_key, cipher)?,
+            faction_ids: entry.faction_ids.clone(),
+        });
+    }
+
     Ok(sources)
 }
 
+/// Revive keys may be given encrypted (see `--seal-key`); plaintext still works but is warned about.
+fn open_api_key(field: &str, value: &str, cipher: &KeyCipher) -> anyhow::Result<String> {
+    if !KeyCipher::is_sealed(value) {
+        log::warn!("{field} is stored in plaintext; run with `--seal-key <key>` to get an encrypted value");
+    }
+    cipher
+        .open_or_plain(value)
+        .with_context(|| format!("Failed to decrypt {field}"))
+}
+
 fn select_secrets_path() -> anyhow::Result<String> {
     let args: Vec<String> = env::args().skip(1).collect();
 
@@ -209,6 +299,9 @@ pub struct Data {
     pub secrets: Secrets,
     pub torn_api: Arc<TornAPI>,
     pub revive_monitor: Arc<ReviveMonitor>,
+    pub shutdown: Shutdown,
+    /// Commands still running, keyed by invocation id, so shutdown waits for them
+    pub running_commands: Mutex<HashMap<u64, WorkGuard>>,
     /// Map of messages sent to the reviver channel, keyed by the user that asked for a revive
     pub revive_responses: Mutex<HashMap<UserId, Message>>,
     /// Map of the original /reviveme interactions, keyed by the reviver-channel message id
@@ -220,11 +313,18 @@ pub struct Data {
 }
 
 impl Data {
-    pub fn new(secrets: Secrets, torn_api: TornAPI, revive_monitor: Arc<ReviveMonitor>) -> Self {
+    pub fn new(
+        secrets: Secrets,
+        torn_api: Arc<TornAPI>,
+        revive_monitor: Arc<ReviveMonitor>,
+        shutdown: Shutdown,
+    ) -> Self {
         Self {
             secrets,
-            torn_api: Arc::new(torn_api),
+            torn_api,
             revive_monitor,
+            shutdown,
+            running_commands: Mutex::new(HashMap::new()),
             revive_responses: Mutex::new(HashMap::new()),
             revive_cancellations: Mutex::new(HashMap::new()),
             contract_pages: Mutex::new(HashMap::new()),
@@ -1,7 +1,46 @@
+use poise::CreateReply;
 use serenity::all::{FullEvent, Interaction};
 
 use crate::bot::commands::{contract, contract_wizard, reviveme, submitkey};
-use crate::bot::data::{Data, Error};
+use crate::bot::data::{Context, Data, Error};
+
+/// Turns commands away once shutdown has begun.
+pub async fn command_check(ctx: Context<'_>) -> Result<bool, Error> {
+    if !ctx.data().shutdown.is_triggered() {
+        return Ok(true);
+    }
+
+    ctx.send(
+        CreateReply::default()
+            .content("Deathfr is restarting. Please try again in a minute.")
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(false)
+}
+
+/// Registers the command as running, so shutdown waits for it to finish.
+pub async fn pre_command(ctx: Context<'_>) {
+    if let Some(work) = ctx.data().shutdown.begin() {
+        ctx.data().running_commands.lock().await.insert(ctx.id(), work);
+    }
+}
+
+pub async fn post_command(ctx: Context<'_>) {
+    ctx.data().running_commands.lock().await.remove(&ctx.id());
+}
+
+/// Releases a failed command's shutdown registration, then reports the error as poise
+/// would by default.
+pub async fn on_error(error: poise::FrameworkError<'_, Data, Error>) {
+    if let Some(ctx) = error.ctx() {
+        ctx.data().running_commands.lock().await.remove(&ctx.id());
+    }
+
+    if let Err(e) = poise::builtins::on_error(error).await {
+        log::error!("Error while handling error: {e}");
+    }
+}
 
 /// Handles everything poise does not route itself: component/modal interactions
 /// belonging to the commands.
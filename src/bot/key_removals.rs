@@ -0,0 +1,69 @@
+use std::sync::Arc;
+
+use mongodb::bson::doc;
+use serenity::all::{CreateMessage, Http, MessageBuilder, UserId};
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+
+use crate::bot::Secrets;
+use crate::database::structures::APIKey;
+use crate::database::Database;
+use crate::torn_api::RemovedKey;
+
+/// Listens for keys Torn rejected and lets the donor know they need to submit a new one.
+///
+/// Keys donated before the donor was recorded fall back to notifying the bot owner.
+pub async fn run_notifier(http: Arc<Http>, secrets: Secrets, mut removals: Receiver<RemovedKey>) {
+    loop {
+        let removed = match removals.recv().await {
+            Ok(removed) => removed,
+            Err(RecvError::Lagged(skipped)) => {
+                log::warn!("Missed {skipped} API key removal notification(s)");
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+
+        if let Err(e) = notify_removed(&http, &secrets, &removed).await {
+            log::error!("Failed to notify about removed API key of {}: {e:#}", removed.owner);
+        }
+    }
+}
+
+async fn notify_removed(http: &Http, secrets: &Secrets, removed: &RemovedKey) -> anyhow::Result<()> {
+    // Keys that never came from `api_keys` (revive monitor, dev key) have no donor.
+    let Some(id) = removed.db_id else {
+        return Ok(());
+    };
+
+    let donor = Database::get_collection_with_filter::<APIKey>(Some(doc! { "_id": id }))
+        .await?
+        .pop()
+        .and_then(|key| key.discord_id);
+
+    let (recipient, content) = match donor {
+        Some(discord_id) => (
+            discord_id,
+            MessageBuilder::new()
+                .push("Your Torn API key donated to Deathfr stopped working and was removed (")
+                .push(format!("error {}: {}", removed.code, removed.message))
+                .push(").\nIf you'd like to keep helping, please submit a new key with ")
+                .push_mono("/submitkey")
+                .push(".")
+                .build(),
+        ),
+        None => (
+            secrets.owner_id,
+            MessageBuilder::new()
+                .push(format!("Donated API key of {} was removed (", removed.owner))
+                .push(format!("error {}: {}", removed.code, removed.message))
+                .push("). The donor is unknown, so they could not be told to submit a new key.")
+                .build(),
+        ),
+    };
+
+    UserId::new(recipient)
+        .direct_message(http, CreateMessage::new().content(content))
+        .await?;
+
+    Ok(())
+}
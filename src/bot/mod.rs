@@ -1,7 +1,14 @@
+pub(crate) mod announcements;
 pub mod auth;
+pub(crate) mod budget_watch;
 pub mod commands;
+pub(crate) mod contract_scheduler;
 pub mod data;
 pub mod handler;
+pub(crate) mod key_removals;
+pub(crate) mod monitor_alerts;
+pub(crate) mod revive_backfill;
+pub(crate) mod revive_log;
 pub(crate) mod startup;
 mod tools;
 
@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use serenity::all::{ChannelId, CreateMessage, Http, MessageBuilder, UserId};
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+
+use crate::bot::Secrets;
+use crate::torn_api::MonitorAlert;
+
+/// Forwards revive monitor alerts to the ops channel, mentioning the owner, or straight
+/// to the owner when no ops channel is configured.
+pub async fn run_alerts(http: Arc<Http>, secrets: Secrets, mut alerts: Receiver<MonitorAlert>) {
+    loop {
+        let alert = match alerts.recv().await {
+            Ok(alert) => alert,
+            Err(RecvError::Lagged(skipped)) => {
+                log::warn!("Missed {skipped} revive monitor alert(s)");
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+
+        if let Err(e) = send_alert(&http, &secrets, &alert).await {
+            log::error!("Failed to send revive monitor alert {alert:?}: {e:#}");
+        }
+    }
+}
+
+fn describe(alert: &MonitorAlert) -> String {
+    match alert {
+        MonitorAlert::SyncFailing {
+            faction_id,
+            failures,
+            error,
+        } => MessageBuilder::new()
+            .push(format!(
+                "⚠️ Revive sync for faction {faction_id} failed {failures} times in a row: "
+            ))
+            .push_mono_safe(error)
+            .build(),
+        MonitorAlert::SyncRecovered {
+            faction_id,
+            failures,
+        } => format!("✅ Revive sync for faction {faction_id} works again after {failures} failures."),
+        MonitorAlert::Stale {
+            faction_id,
+            synced_until,
+        } if *synced_until == 0 => {
+            format!("⚠️ Revives of faction {faction_id} have never been fully synced.")
+        }
+        MonitorAlert::Stale {
+            faction_id,
+            synced_until,
+        } => format!(
+            "⚠️ Revives of faction {faction_id} are only stored up to <t:{synced_until}:f> (<t:{synced_until}:R>)."
+        ),
+        MonitorAlert::StaleRecovered { faction_id } => {
+            format!("✅ Revives of faction {faction_id} are up to date again.")
+        }
+        MonitorAlert::LoopStalled { last_update } => format!(
+            "⚠️ The revive monitor has not finished a sync since <t:{last_update}:f> (<t:{last_update}:R>)."
+        ),
+    }
+}
+
+async fn send_alert(http: &Http, secrets: &Secrets, alert: &MonitorAlert) -> anyhow::Result<()> {
+    let content = describe(alert);
+
+    match secrets.ops_channel {
+        Some(channel) => {
+            ChannelId::new(channel)
+                .send_message(
+                    http,
+                    CreateMessage::new().content(format!("<@{}> {content}", secrets.owner_id)),
+                )
+                .await?;
+        }
+        None => {
+            UserId::new(secrets.owner_id)
+                .direct_message(http, CreateMessage::new().content(content))
+                .await?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,278 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use mongodb::bson::{self, doc};
+use serenity::all::{ChannelId, CreateMessage, EditMessage, Http, MessageId, UserId};
+
+use crate::bot::tools::contract_events::record_event;
+use crate::database::structures::{
+    BackfillStatus, Contract, ContractEventKind, ReviveBackfill, Status,
+};
+use crate::database::Database;
+use crate::shutdown::Shutdown;
+use crate::torn_api::{ReviveMonitor, TornError};
+
+/// Picks up imports that were still running when the bot stopped.
+pub async fn resume_backfills(http: Arc<Http>, monitor: Arc<ReviveMonitor>, shutdown: Shutdown) {
+    let running = match Database::get_collection_with_filter::<ReviveBackfill>(Some(doc! {
+        "status": bson::to_bson(&BackfillStatus::Running).unwrap()
+    }))
+    .await
+    {
+        Ok(running) => running,
+        Err(e) => {
+            log::error!("Failed to load unfinished revive backfills: {e:#}");
+            return;
+        }
+    };
+
+    for job in running {
+        log::info!(
+            "Resuming revive backfill of faction {} at {}",
+            job.faction_id,
+            job.cursor
+        );
+        tokio::spawn(run_backfill(
+            http.clone(),
+            monitor.clone(),
+            job,
+            shutdown.clone(),
+        ));
+    }
+}
+
+/// First wait before retrying a page that failed for a transient reason.
+const RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Longest wait between retries of a page.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(15 * 60);
+
+/// Imports the job's window page by page, storing the cursor and editing the progress
+/// message after each one. On shutdown it stops between pages and stays `running`, so
+/// it resumes on the next start.
+///
+/// Pages that fail because Torn is unreachable or no key is free are retried with a
+/// growing delay; only errors retrying can't fix (Torn rejecting the keys, a response
+/// that doesn't parse) stop the import.
+pub async fn run_backfill(
+    http: Arc<Http>,
+    monitor: Arc<ReviveMonitor>,
+    mut job: ReviveBackfill,
+    shutdown: Shutdown,
+) {
+    let mut retry_delay = RETRY_DELAY;
+
+    loop {
+        let Some(work) = shutdown.begin() else {
+            log::info!(
+                "Revive backfill of faction {} paused for shutdown at {}",
+                job.faction_id,
+                job.cursor
+            );
+            return;
+        };
+
+        match monitor
+            .backfill_page(job.faction_id, job.cursor, job.to)
+            .await
+        {
+            Ok(page) => {
+                retry_delay = RETRY_DELAY;
+                job.inserted += page.inserted as u64;
+                job.cursor = page.next;
+                if page.finished {
+                    job.status = BackfillStatus::Done;
+                    job.gap_open = false;
+                }
+            }
+            Err(e) if is_transient(&e) => {
+                let delay = e
+                    .downcast_ref::<TornError>()
+                    .and_then(|e| match e {
+                        TornError::Unavailable(unavailable) => unavailable.retry_after,
+                        _ => None,
+                    })
+                    .unwrap_or(retry_delay)
+                    .max(retry_delay);
+                log::warn!(
+                    "Revive backfill of faction {} failed at {}, retrying in {}s: {e:#}",
+                    job.faction_id,
+                    job.cursor,
+                    delay.as_secs()
+                );
+                retry_delay = (retry_delay * 2).min(MAX_RETRY_DELAY);
+
+                drop(work);
+                if !shutdown.sleep(delay).await {
+                    return;
+                }
+                continue;
+            }
+            Err(e) => {
+                log::error!(
+                    "Revive backfill of faction {} failed at {}: {e:#}",
+                    job.faction_id,
+                    job.cursor
+                );
+                job.status = BackfillStatus::Failed;
+                job.error = Some(format!("{e:#}"));
+            }
+        }
+
+        if let Err(e) = Database::update(job.clone(), doc! { "_id": job.id }).await {
+            log::error!("Failed to store revive backfill progress: {e:#}");
+        }
+
+        let mut reset = 0;
+        if job.status == BackfillStatus::Done {
+            log::info!(
+                "Revive backfill of faction {} finished, {} revives stored",
+                job.faction_id,
+                job.inserted
+            );
+            close_covered_gaps(&job).await;
+            if job.inserted > 0 {
+                reset = reset_contracts(&job).await;
+                // Active contracts with a budget are priced again by the budget watch.
+                monitor.backfill_finished(job.faction_id);
+            }
+        }
+
+        if let Err(e) = show_progress(&http, &job, reset).await {
+            log::warn!("Failed to update revive backfill message: {e:#}");
+        }
+
+        if job.status != BackfillStatus::Running {
+            if let Err(e) = notify_requester(&http, &job, reset).await {
+                log::warn!("Failed to tell the requester about a revive backfill: {e:#}");
+            }
+            return;
+        }
+    }
+}
+
+/// Whether a failed page is worth trying again: Torn was unreachable, no key was free,
+/// or storing the revives failed. Errors Torn answered with are not.
+fn is_transient(error: &anyhow::Error) -> bool {
+    match error.downcast_ref::<TornError>() {
+        Some(TornError::Unavailable(_) | TornError::Network(_)) => true,
+        Some(TornError::Api(_) | TornError::Parsing(_)) => false,
+        None => true,
+    }
+}
+
+/// Earlier imports of the faction that failed partway leave a gap behind; a finished
+/// import that covers the rest of their window closes it.
+async fn close_covered_gaps(job: &ReviveBackfill) {
+    let result = Database::update_docs::<ReviveBackfill>(
+        doc! {
+            "faction_id": job.faction_id as i64,
+            "gap_open": true,
+            "cursor": { "$gte": job.from as i64 },
+            "to": { "$lte": job.to as i64 },
+        },
+        doc! { "$set": { "gap_open": false } },
+    )
+    .await;
+
+    if let Err(e) = result {
+        log::error!("Failed to close revive gaps covered by a backfill: {e:#}");
+    }
+}
+
+/// Marks ended contracts overlapping the imported window so their report re-syncs and
+/// their money is settled again with the new revives.
+async fn reset_contracts(job: &ReviveBackfill) -> u64 {
+    let filter = doc! {
+        "status": bson::to_bson(&Status::Ended).unwrap(),
+        "started": { "$lte": job.to as i64 },
+        "ended": { "$gte": job.from as i64 },
+    };
+
+    let affected = match Database::get_collection_with_filter::<Contract>(Some(filter.clone())).await {
+        Ok(affected) => affected,
+        Err(e) => {
+            log::error!("Failed to find contracts to recalculate after a backfill: {e:#}");
+            return 0;
+        }
+    };
+
+    let result = Database::update_docs::<Contract>(
+        filter,
+        doc! { "$set": { "revives_synced": false, "money_settled": false } },
+    )
+    .await;
+
+    match result {
+        Ok(count) => {
+            for contract in &affected {
+                record_event(
+                    &contract.contract_id,
+                    ContractEventKind::BackfillReset,
+                    Some(job.requested_by),
+                    doc! {
+                        "faction_id": job.faction_id as i64,
+                        "from": job.from as i64,
+                        "to": job.to as i64,
+                        "inserted": job.inserted as i64,
+                    },
+                )
+                .await;
+            }
+            count
+        }
+        Err(e) => {
+            log::error!("Failed to mark contracts for recalculation after a backfill: {e:#}");
+            0
+        }
+    }
+}
+
+pub fn progress_text(job: &ReviveBackfill, reset: u64) -> String {
+    let header = format!(
+        "Revive backfill of faction {} from <t:{}:f> to <t:{}:f>",
+        job.faction_id, job.from, job.to
+    );
+
+    match job.status {
+        BackfillStatus::Running => {
+            let span = job.to.saturating_sub(job.from).max(1);
+            let done = job.cursor.saturating_sub(job.from).min(span);
+            format!(
+                "{header}\n⏳ {}% — imported up to <t:{}:f>, {} new revives so far.",
+                done * 100 / span,
+                job.cursor.min(job.to),
+                job.inserted
+            )
+        }
+        BackfillStatus::Done => format!(
+            "{header}\n✅ Finished: {} new revives stored, {reset} ended contract(s) will be recalculated.",
+            job.inserted
+        ),
+        BackfillStatus::Failed => format!(
+            "{header}\n❌ Stopped at <t:{}:f> after {} new revives: {}\nStart it again from that date to continue.",
+            job.cursor,
+            job.inserted,
+            job.error.as_deref().unwrap_or("unknown error")
+        ),
+    }
+}
+
+/// DMs whoever started the import once it finished or stopped.
+async fn notify_requester(http: &Http, job: &ReviveBackfill, reset: u64) -> anyhow::Result<()> {
+    UserId::new(job.requested_by)
+        .direct_message(http, CreateMessage::new().content(progress_text(job, reset)))
+        .await?;
+    Ok(())
+}
+
+async fn show_progress(http: &Http, job: &ReviveBackfill, reset: u64) -> anyhow::Result<()> {
+    ChannelId::new(job.channel_id)
+        .edit_message(
+            http,
+            MessageId::new(job.message_id),
+            EditMessage::new().content(progress_text(job, reset)),
+        )
+        .await?;
+    Ok(())
+}
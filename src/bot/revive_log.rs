@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use mongodb::bson::{doc, Bson};
+use serenity::all::{ChannelId, CreateEmbed, CreateMessage, Http};
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+
+use crate::bot::tools::get_player_cache::get_player_cache;
+use crate::bot::Secrets;
+use crate::bot::tools::contract_scope::resolve_scope;
+use crate::database::structures::{Contract, ReviveEntry};
+use crate::database::Database;
+use crate::pricing::{classify_revive, ReviveClass};
+use crate::torn_api::{Priority, TornAPI};
+
+/// Revives listed in one embed, keeping the description under Discord's limit.
+const REVIVES_PER_EMBED: usize = 15;
+
+/// Posts newly synced revives that fall under a contract to the revive log channel, so
+/// leadership can follow a contract without generating a report.
+pub async fn run_revive_log(
+    http: Arc<Http>,
+    secrets: Secrets,
+    api: Arc<TornAPI>,
+    channel: ChannelId,
+    mut revives: Receiver<Vec<ReviveEntry>>,
+) {
+    loop {
+        let batch = match revives.recv().await {
+            Ok(batch) => batch,
+            Err(RecvError::Lagged(skipped)) => {
+                log::warn!("Revive log missed {skipped} batch(es) of revives");
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+
+        if let Err(e) = post_batch(&http, &secrets, &api, channel, batch).await {
+            log::error!("Failed to post revives to the revive log: {e:#}");
+        }
+    }
+}
+
+/// Whether `contract` covers a revive made at `timestamp`. Pending contracts are only
+/// promoted lazily, so they count once their start time has passed.
+fn covers(contract: &Contract, timestamp: u64) -> bool {
+    contract.started <= timestamp
+        && (contract.ended == 0 || timestamp <= contract.ended)
+        && !contract.is_paused_at(timestamp)
+}
+
+async fn post_batch(
+    http: &Http,
+    secrets: &Secrets,
+    api: &TornAPI,
+    channel: ChannelId,
+    batch: Vec<ReviveEntry>,
+) -> anyhow::Result<()> {
+    let reviving_factions = secrets.reviving_faction_ids();
+    let revives: Vec<ReviveEntry> = batch
+        .into_iter()
+        .filter(|revive| reviving_factions.contains(&revive.reviver_faction))
+        .collect();
+    let Some(oldest) = revives.iter().map(|revive| revive.timestamp).min() else {
+        return Ok(());
+    };
+
+    let mut targets: Vec<u64> = revives.iter().map(|revive| revive.target_faction).collect();
+    targets.sort_unstable();
+    targets.dedup();
+    let targets: Vec<Bson> = targets.into_iter().map(|id| Bson::Int64(id as i64)).collect();
+
+    let contracts = Database::get_collection_with_filter::<Contract>(Some(doc! {
+        "$and": [
+            { "$or": [
+                { "faction_id": { "$in": targets.clone() } },
+                { "faction_ids": { "$in": targets } },
+            ] },
+            { "$or": [
+                { "ended": 0_i64 },
+                { "ended": { "$gte": oldest as i64 } },
+            ] },
+        ],
+    }))
+    .await?;
+
+    let mut names: HashMap<u64, String> = HashMap::new();
+
+    for contract in &contracts {
+        let target_factions = contract.target_factions();
+        let covered: Vec<&ReviveEntry> = revives
+            .iter()
+            .filter(|revive| {
+                target_factions.contains(&revive.target_faction)
+                    && covers(contract, revive.timestamp)
+            })
+            .collect();
+        if covered.is_empty() {
+            continue;
+        }
+
+        let scope = resolve_scope(contract, covered.iter().copied(), api, Priority::Background).await?;
+
+        let mut lines = Vec::with_capacity(covered.len());
+        for revive in &covered {
+            let reviver = player_name(api, &mut names, revive.reviver_id).await;
+            let target = player_name(api, &mut names, revive.target_id).await;
+            let counts = match classify_revive(revive, contract.min_chance, &scope) {
+                ReviveClass::Success => "✅ counts",
+                ReviveClass::FailedCounted => "✅ counts (failed)",
+                ReviveClass::Ignored => "➖ not counted",
+                ReviveClass::OutOfScope => "➖ out of scope",
+            };
+            lines.push(format!(
+                "<t:{}:T> [{reviver}]({}) → [{target}]({}) · {:.2}% · {} · {counts}",
+                revive.timestamp,
+                player_link(revive.reviver_id),
+                player_link(revive.target_id),
+                revive.chance,
+                revive.result
+            ));
+        }
+
+        for chunk in lines.chunks(REVIVES_PER_EMBED) {
+            let embed = CreateEmbed::new()
+                .title(format!(
+                    "{} ({})",
+                    contract.contract_name, contract.contract_id
+                ))
+                .description(chunk.join("\n"));
+            channel
+                .send_message(http, CreateMessage::new().embed(embed))
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn player_name(api: &TornAPI, names: &mut HashMap<u64, String>, id: u64) -> String {
+    if let Some(name) = names.get(&id) {
+        return name.clone();
+    }
+
+    let name = get_player_cache(id, api, Priority::Background)
+        .await
+        .map(|player| player.name)
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| id.to_string());
+    names.insert(id, name.clone());
+    name
+}
+
+fn player_link(id: u64) -> String {
+    format!("https://www.torn.com/profiles.php?XID={}", id)
+}
@@ -0,0 +1,273 @@
+use chrono::Utc;
+use mongodb::bson::doc;
+
+use crate::bot::tools::contract_lifecycle::resettle_contract;
+use crate::database::structures::{Contract, ContractEdit, Status};
+use crate::database::Database;
+use crate::pricing::{format_with_commas, PricingType};
+use crate::torn_api::TornAPI;
+
+/// New values for the terms of a contract; `None` leaves a term as it is.
+#[derive(Debug, Default)]
+pub struct ContractChanges {
+    pub contract_name: Option<String>,
+    pub min_chance: Option<u64>,
+    pub pricing_type: Option<PricingType>,
+    pub faction_cut: Option<i64>,
+    pub started: Option<u64>,
+    /// `Some(0)` removes the budget.
+    pub budget: Option<u64>,
+    pub end_at_budget: Option<bool>,
+}
+
+/// Terms that only drive budget alerts and don't change what the revives cost.
+const BUDGET_FIELDS: [&str; 2] = ["budget", "end_at_budget"];
+
+/// Checks the changes against the contract, returning a message for the admin when
+/// they can't be applied.
+pub fn check_changes(contract: &Contract, changes: &ContractChanges) -> Result<(), String> {
+    if changes.contract_name.as_deref().is_some_and(|name| name.trim().is_empty()) {
+        return Err("The contract name can't be empty.".to_string());
+    }
+    if changes.min_chance.is_some_and(|min_chance| min_chance > 100) {
+        return Err("The minimum chance must be between 0 and 100.".to_string());
+    }
+    if changes.faction_cut.is_some_and(|cut| cut < 0) {
+        return Err("The faction cut can't be negative.".to_string());
+    }
+
+    let budget = match changes.budget {
+        Some(budget) => Some(budget).filter(|&budget| budget > 0),
+        None => contract.budget,
+    };
+    if changes.end_at_budget.unwrap_or(contract.end_at_budget) && budget.is_none() {
+        return Err("Ending at the budget needs a budget.".to_string());
+    }
+
+    if let Some(started) = changes.started {
+        let now = Utc::now().timestamp() as u64;
+        match contract.status {
+            Status::Active if started > now => {
+                return Err("An active contract can't be moved to start in the future.".to_string());
+            }
+            Status::Ended if started >= contract.ended => {
+                return Err("The start time must be before the contract ended.".to_string());
+            }
+            _ => {}
+        }
+        if contract.end_time.is_some_and(|end_time| started >= end_time) {
+            return Err("The start time must be before the contract's end time.".to_string());
+        }
+        if let Some(pause) = contract.pauses.first().filter(|pause| started > pause.from) {
+            return Err(format!(
+                "The start time must be before the contract was first paused (<t:{}:f>).",
+                pause.from
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Applies checked changes, stores the contract and one [`ContractEdit`] per changed term,
+/// and returns those edits (none when every value was already current).
+///
+/// Changing a term that affects the price clears `money_settled`; an ended contract is
+/// then settled again with [`resettle_if_ended`].
+pub async fn edit_contract(
+    contract: &mut Contract,
+    changes: ContractChanges,
+    edited_by: u64,
+) -> anyhow::Result<Vec<ContractEdit>> {
+    let edited_at = Utc::now().timestamp() as u64;
+    let contract_id = contract.contract_id.clone();
+    let mut edits = Vec::new();
+    let mut record = |field: &str, old_value: String, new_value: String| {
+        edits.push(ContractEdit {
+            id: None,
+            contract_id: contract_id.clone(),
+            field: field.to_string(),
+            old_value,
+            new_value,
+            edited_by,
+            edited_at,
+        });
+    };
+
+    if let Some(name) = changes.contract_name.map(|name| name.trim().to_string()) {
+        if name != contract.contract_name {
+            record("contract_name", contract.contract_name.clone(), name.clone());
+            contract.contract_name = name;
+        }
+    }
+    if let Some(min_chance) = changes.min_chance {
+        if min_chance != contract.min_chance {
+            record("min_chance", contract.min_chance.to_string(), min_chance.to_string());
+            contract.min_chance = min_chance;
+        }
+    }
+    if let Some(pricing_type) = changes.pricing_type {
+        if pricing_type != contract.pricing_type {
+            record(
+                "pricing_type",
+                contract.pricing_type.label().to_string(),
+                pricing_type.label().to_string(),
+            );
+            contract.pricing_type = pricing_type;
+        }
+    }
+    if let Some(faction_cut) = changes.faction_cut {
+        if faction_cut != contract.faction_cut {
+            record("faction_cut", contract.faction_cut.to_string(), faction_cut.to_string());
+            contract.faction_cut = faction_cut;
+        }
+    }
+    if let Some(started) = changes.started {
+        if started != contract.started {
+            record("started", contract.started.to_string(), started.to_string());
+            contract.started = started;
+        }
+    }
+
+    if let Some(budget) = changes.budget.map(|budget| Some(budget).filter(|&budget| budget > 0)) {
+        if budget != contract.budget {
+            let label = |budget: Option<u64>| budget.map_or("none".to_string(), |budget| budget.to_string());
+            record("budget", label(contract.budget), label(budget));
+            contract.budget = budget;
+            // Alert again against the new budget.
+            contract.budget_alerted = 0;
+        }
+    }
+    if let Some(end_at_budget) = changes.end_at_budget {
+        if end_at_budget != contract.end_at_budget {
+            record(
+                "end_at_budget",
+                contract.end_at_budget.to_string(),
+                end_at_budget.to_string(),
+            );
+            contract.end_at_budget = end_at_budget;
+        }
+    }
+
+    if edits.is_empty() {
+        return Ok(edits);
+    }
+
+    if edits
+        .iter()
+        .any(|edit| edit.field != "contract_name" && !BUDGET_FIELDS.contains(&edit.field.as_str()))
+    {
+        contract.money_settled = false;
+    }
+
+    Database::update(contract.clone(), doc! {"contract_id": contract.contract_id.clone()})
+        .await?;
+    let edits = Database::insert_manny(edits).await?;
+
+    log::info!(
+        "Contract {} edited by {edited_by}: {}",
+        contract.contract_id,
+        edits
+            .iter()
+            .map(|edit| format!("{} {} -> {}", edit.field, edit.old_value, edit.new_value))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+
+    Ok(edits)
+}
+
+/// Settles an ended contract again right after an edit cleared `money_settled`, and
+/// returns a line for the reply saying how that went. `before` is the contract as it was
+/// before the edit.
+pub async fn resettle_if_ended(
+    contract: &mut Contract,
+    before: &Contract,
+    reviving_faction_ids: &[u64],
+    api: &TornAPI,
+    edited_by: u64,
+) -> Option<String> {
+    if contract.status != Status::Ended || contract.money_settled {
+        return None;
+    }
+
+    let line = match resettle_contract(contract, before, reviving_faction_ids, api, Some(edited_by)).await {
+        Ok(settlement) => format!(
+            "Its money was settled again with the new terms: {} revives, ${} before the faction cut.",
+            settlement.revives,
+            format_with_commas(settlement.money)
+        ),
+        Err(e) => {
+            log::error!("Failed to settle edited contract {} again: {e:#}", contract.contract_id);
+            format!("Settling its money again failed ({e:#}); it is retried when the next contract ends.")
+        }
+    };
+    Some(line)
+}
+
+/// "field: old → new" lines for a reply, with times shown as Discord timestamps.
+pub fn describe_edits(edits: &[ContractEdit]) -> String {
+    edits
+        .iter()
+        .map(|edit| {
+            let (old_value, new_value) = if edit.field == "started" {
+                (
+                    format!("<t:{}:f>", edit.old_value),
+                    format!("<t:{}:f>", edit.new_value),
+                )
+            } else {
+                (edit.old_value.clone(), edit.new_value.clone())
+            };
+            format!("* `{}`: {old_value} → {new_value}", edit.field)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::structures::Pause;
+    use crate::pricing::ContractScope;
+
+    fn paused_contract() -> Contract {
+        Contract {
+            id: None,
+            contract_id: "abc123".to_string(),
+            contract_name: "Test".to_string(),
+            faction_id: 1,
+            faction_ids: vec![1],
+            min_chance: 50,
+            scope: ContractScope::All,
+            started: 1_000,
+            ended: 10_000,
+            end_time: None,
+            status: Status::Ended,
+            faction_cut: 10,
+            pricing_type: PricingType::Legacy,
+            revives_synced: true,
+            money_settled: true,
+            budget: None,
+            end_at_budget: false,
+            budget_alerted: 0,
+            pauses: vec![Pause {
+                from: 2_000,
+                to: Some(3_000),
+            }],
+        }
+    }
+
+    #[test]
+    fn start_can_not_move_past_the_first_pause() {
+        let contract = paused_contract();
+        let change = |started| ContractChanges {
+            started: Some(started),
+            ..Default::default()
+        };
+
+        assert!(check_changes(&contract, &change(1_500)).is_ok());
+        assert!(check_changes(&contract, &change(2_000)).is_ok());
+        assert!(check_changes(&contract, &change(2_001)).is_err());
+        assert!(check_changes(&contract, &change(4_000)).is_err());
+    }
+}
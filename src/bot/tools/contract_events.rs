@@ -0,0 +1,77 @@
+use chrono::Utc;
+use mongodb::bson::{doc, Bson, Document};
+
+use crate::database::structures::{Contract, ContractEdit, ContractEvent, ContractEventKind};
+use crate::database::Database;
+
+/// Stores a lifecycle event of a contract. Failing to store it is logged and never fails
+/// the action that caused it.
+pub async fn record_event(
+    contract_id: &str,
+    kind: ContractEventKind,
+    actor: Option<u64>,
+    payload: Document,
+) {
+    let event = ContractEvent {
+        id: None,
+        contract_id: contract_id.to_string(),
+        kind,
+        actor,
+        timestamp: Utc::now().timestamp() as u64,
+        payload,
+    };
+
+    if let Err(e) = Database::insert(event).await {
+        log::error!("Failed to record {kind:?} event of contract {contract_id}: {e:#}");
+    }
+}
+
+/// The terms a contract was created with, as an event payload.
+pub fn contract_terms(contract: &Contract) -> Document {
+    let mut terms = doc! {
+        "contract_name": &contract.contract_name,
+        "faction_ids": contract
+            .target_factions()
+            .iter()
+            .map(|id| Bson::Int64(*id as i64))
+            .collect::<Vec<_>>(),
+        "min_chance": contract.min_chance as i64,
+        "scope": contract.scope.label(),
+        "pricing_type": contract.pricing_type.label(),
+        "faction_cut": contract.faction_cut,
+        "started": contract.started as i64,
+    };
+    if let Some(end_time) = contract.end_time {
+        terms.insert("end_time", end_time as i64);
+    }
+    if let Some(budget) = contract.budget {
+        terms.insert("budget", budget as i64);
+        terms.insert("end_at_budget", contract.end_at_budget);
+    }
+    terms
+}
+
+/// Edits stored in `contract_edits` as `Edited` events for the history, one per edit
+/// command: terms changed at the same time by the same admin are grouped.
+pub fn edit_events(edits: &[ContractEdit]) -> Vec<ContractEvent> {
+    let mut events: Vec<ContractEvent> = Vec::new();
+    for edit in edits {
+        let change = doc! { "old": &edit.old_value, "new": &edit.new_value };
+        match events.last_mut() {
+            Some(event)
+                if event.timestamp == edit.edited_at && event.actor == Some(edit.edited_by) =>
+            {
+                event.payload.insert(edit.field.clone(), change);
+            }
+            _ => events.push(ContractEvent {
+                id: None,
+                contract_id: edit.contract_id.clone(),
+                kind: ContractEventKind::Edited,
+                actor: Some(edit.edited_by),
+                timestamp: edit.edited_at,
+                payload: doc! { edit.field.clone(): change },
+            }),
+        }
+    }
+    events
+}
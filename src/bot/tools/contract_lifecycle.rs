@@ -0,0 +1,180 @@
+use crate::bot::tools::contract_events::record_event;
+use crate::bot::tools::settle_money::{clear_contract_money, settle_contract_money, Settlement};
+use crate::database::structures::{Contract, ContractEventKind, Status};
+use crate::database::Database;
+use crate::torn_api::TornAPI;
+use chrono::Utc;
+use mongodb::bson::{self, doc};
+
+/// Activates pending contracts whose start time has passed and returns them.
+///
+/// Only a contract this call moved out of `pending` is returned, so when the scheduler
+/// and `/contract list` promote at the same time it is announced once.
+pub async fn promote_pending_contracts() -> anyhow::Result<Vec<Contract>> {
+    let pending_contracts = Database::get_collection_with_filter::<Contract>(Some(
+        doc! {"status": bson::to_bson(&Status::Pending).unwrap()}
+    ))
+    .await?;
+
+    let now = Utc::now().timestamp() as u64;
+    let mut promoted = Vec::new();
+
+    for mut contract in pending_contracts {
+        if contract.started <= now {
+            let changed = Database::update_docs::<Contract>(
+                doc! {
+                    "contract_id": contract.contract_id.clone(),
+                    "status": bson::to_bson(&Status::Pending).unwrap(),
+                },
+                doc! { "$set": { "status": bson::to_bson(&Status::Active).unwrap() } },
+            )
+            .await?;
+
+            if changed > 0 {
+                log::info!("Contract {} started", contract.contract_id);
+                record_event(
+                    &contract.contract_id,
+                    ContractEventKind::Started,
+                    None,
+                    doc! { "started": contract.started as i64 },
+                )
+                .await;
+                contract.status = Status::Active;
+                promoted.push(contract);
+            }
+        }
+    }
+
+    Ok(promoted)
+}
+
+/// Ends `contract` at `ended` and settles the money of its revives, then settles older
+/// ended contracts that are still unsettled. Used by `/contract end` (with the admin as
+/// `actor`) and by the scheduler when a contract reaches its end time.
+pub async fn end_contract(
+    contract: &mut Contract,
+    ended: u64,
+    reviving_faction_ids: &[u64],
+    api: &TornAPI,
+    actor: Option<u64>,
+) -> anyhow::Result<()> {
+    contract.status = Status::Ended;
+    contract.ended = ended;
+    if let Some(pause) = contract.pauses.last_mut().filter(|pause| pause.to.is_none()) {
+        pause.to = Some(ended.max(pause.from));
+    }
+
+    Database::update(contract.clone(), doc! {"contract_id": contract.contract_id.clone()})
+        .await?;
+    record_event(
+        &contract.contract_id,
+        ContractEventKind::Ended,
+        actor,
+        doc! { "ended": ended as i64 },
+    )
+    .await;
+
+    let settled = settle_contract_money(contract, reviving_faction_ids, api).await;
+    let settlement = match &settled {
+        Ok(settlement) => doc! {
+            "revives": settlement.revives as i64,
+            "money": settlement.money as i64,
+        },
+        Err(e) => {
+            log::error!("Failed to settle money for contract {}: {e:#}", contract.contract_id);
+            doc! { "error": format!("{e:#}") }
+        }
+    };
+    record_event(&contract.contract_id, ContractEventKind::Settled, actor, settlement).await;
+
+    contract.money_settled = true;
+    Database::update(contract.clone(), doc! {"contract_id": contract.contract_id.clone()})
+        .await?;
+
+    let unsettled: Vec<Contract> = Database::get_collection_with_filter(Some(doc! {
+        "status": bson::to_bson(&Status::Ended).unwrap(),
+        "money_settled": { "$ne": true },
+        "contract_id": { "$ne": &contract.contract_id }
+    }))
+    .await?;
+
+    let mut backfill_count = 0u64;
+    for old_contract in &unsettled {
+        match settle_contract_money(old_contract, reviving_faction_ids, api).await {
+            Ok(settlement) => {
+                backfill_count += settlement.revives;
+                record_event(
+                    &old_contract.contract_id,
+                    ContractEventKind::BackfillSettled,
+                    actor,
+                    doc! {
+                        "revives": settlement.revives as i64,
+                        "money": settlement.money as i64,
+                        "during_end_of": &contract.contract_id,
+                    },
+                )
+                .await;
+                let mut c = old_contract.clone();
+                c.money_settled = true;
+                Database::update(c, doc! {"contract_id": old_contract.contract_id.clone()})
+                    .await?;
+            }
+            Err(e) => {
+                log::error!(
+                    "Failed to settle money for old contract {}: {e:#}",
+                    old_contract.contract_id
+                );
+            }
+        }
+    }
+
+    if backfill_count > 0 {
+        log::info!("Backfilled money for {} revives across {} old contracts", backfill_count, unsettled.len());
+    }
+
+    match &settled {
+        Ok(settlement) => log::info!(
+            "Settled money for {} revives under contract {}",
+            settlement.revives,
+            contract.contract_id
+        ),
+        Err(e) => log::error!("Money settlement failed for contract {}: {e:#}", contract.contract_id),
+    }
+
+    Ok(())
+}
+
+/// Settles an ended contract again after an edit changed its terms. Revives of the
+/// window it had `before` the edit are reset first, so ones the edit left out don't keep
+/// their old money.
+pub async fn resettle_contract(
+    contract: &mut Contract,
+    before: &Contract,
+    reviving_faction_ids: &[u64],
+    api: &TornAPI,
+    actor: Option<u64>,
+) -> anyhow::Result<Settlement> {
+    clear_contract_money(before, reviving_faction_ids).await?;
+    let settlement = settle_contract_money(contract, reviving_faction_ids, api).await?;
+    record_event(
+        &contract.contract_id,
+        ContractEventKind::Settled,
+        actor,
+        doc! {
+            "revives": settlement.revives as i64,
+            "money": settlement.money as i64,
+        },
+    )
+    .await;
+
+    contract.money_settled = true;
+    Database::update(contract.clone(), doc! {"contract_id": contract.contract_id.clone()})
+        .await?;
+    log::info!(
+        "Settled money again for {} revives under edited contract {}",
+        settlement.revives,
+        contract.contract_id
+    );
+
+    Ok(settlement)
+}
@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+use std::ops::Add;
+
+use mongodb::bson::doc;
+use torn_api::models::{UserDiscordPathId, UserId};
+
+use crate::database::structures::{AttackerFaction, Contract, ReviveEntry};
+use crate::database::Database;
+use crate::pricing::ResolvedScope;
+use crate::torn_api::{Priority, TornAPI, TornError};
+
+/// Looks up the factions of the attackers the contract's scope depends on, so its
+/// revives can be classified. Only defensive scopes need any lookups.
+///
+/// Factions come from the `attacker_factions` cache first; attackers missing there are
+/// fetched from Torn and cached. An attacker whose lookup fails stays unknown, which
+/// leaves their revives outside a defensive scope rather than failing the whole call.
+pub async fn resolve_scope<'a>(
+    contract: &Contract,
+    revives: impl IntoIterator<Item = &'a ReviveEntry>,
+    api: &TornAPI,
+    priority: Priority,
+) -> anyhow::Result<ResolvedScope> {
+    let attackers = contract.scope.attackers_to_resolve(revives);
+    if attackers.is_empty() {
+        return Ok(ResolvedScope::new(contract.scope.clone(), HashMap::new()));
+    }
+
+    let cached: Vec<AttackerFaction> = Database::get_collection_with_filter(Some(doc! {
+        "user_id": { "$in": attackers.iter().map(|&id| id as i64).collect::<Vec<_>>() },
+    }))
+    .await?;
+
+    let mut attacker_factions = HashMap::new();
+    let mut known = Vec::with_capacity(cached.len());
+    for entry in cached {
+        known.push(entry.user_id);
+        if let Some(faction_id) = entry.faction_id {
+            attacker_factions.insert(entry.user_id, faction_id);
+        }
+    }
+
+    for attacker_id in attackers.into_iter().filter(|id| !known.contains(id)) {
+        let response = match api
+            .get_player_faction(UserDiscordPathId::UserId(UserId::new(attacker_id as i32)), priority)
+            .await
+        {
+            Ok(response) => response,
+            // The rest would fail the same way.
+            Err(e @ TornError::Unavailable(_)) => {
+                log::warn!("Stopped looking up attacker factions: {e}");
+                break;
+            }
+            Err(e) => {
+                log::warn!("Failed to look up the faction of attacker {attacker_id}: {e:#}");
+                continue;
+            }
+        };
+
+        let faction_id = response.faction.map(|faction| faction.id.0 as u64);
+        if let Some(faction_id) = faction_id {
+            attacker_factions.insert(attacker_id, faction_id);
+        }
+
+        let entry = AttackerFaction {
+            user_id: attacker_id,
+            faction_id,
+            expire_at: chrono::Utc::now().add(chrono::Duration::days(30)),
+        };
+        if let Err(e) = Database::insert(entry).await {
+            log::warn!("Failed to cache the faction of attacker {attacker_id}: {e:#}");
+        }
+    }
+
+    Ok(ResolvedScope::new(contract.scope.clone(), attacker_factions))
+}
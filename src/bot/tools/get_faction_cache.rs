@@ -0,0 +1,40 @@
+use crate::database::structures::FactionCache;
+use crate::database::Database;
+use crate::torn_api::{Priority, TornAPI, TornError};
+use mongodb::bson::doc;
+use std::ops::Add;
+use torn_api::models::FactionId;
+
+/// Cached lookup of a Torn faction's name. Fetches from Torn (and stores a 1-day cache)
+/// when the faction is not yet in the database; Torn errors are passed through.
+pub async fn get_faction_cache(
+    faction_id: u64,
+    api: &TornAPI,
+    priority: Priority,
+) -> Result<FactionCache, TornError> {
+    let db_result: Vec<FactionCache> =
+        Database::get_collection_with_filter(Some(doc! { "faction_id": faction_id as i64 }))
+            .await
+            .unwrap();
+
+    if let Some(existing) = db_result.first() {
+        return Ok(existing.clone());
+    }
+
+    let resp = api
+        .get_faction_basic(FactionId::new(faction_id as i32), priority)
+        .await?;
+
+    let faction_cache = FactionCache {
+        faction_id,
+        name: resp.basic.name,
+        expire_at: chrono::Utc::now().add(chrono::Duration::days(1)),
+    };
+
+    // Two lookups of the same faction can race here; the unique index keeps one copy.
+    if let Err(e) = Database::insert(faction_cache.clone()).await {
+        log::warn!("Failed to cache faction {faction_id}: {e:#}");
+    }
+
+    Ok(faction_cache)
+}
@@ -1,13 +1,17 @@
 use crate::database::structures::PlayerCache;
 use crate::database::Database;
-use crate::torn_api::TornAPI;
+use crate::torn_api::{Priority, TornAPI};
 use mongodb::bson::doc;
 use std::ops::Add;
 use torn_api::models::{UserDiscordPathId, UserId};
 
 /// Cached lookup of a Torn player's display name. Fetches from Torn (and stores a
 /// 7-day cache) when the player is not yet in the database.
-pub async fn get_player_cache(user_id: u64, api: &TornAPI) -> Option<PlayerCache> {
+pub async fn get_player_cache(
+    user_id: u64,
+    api: &TornAPI,
+    priority: Priority,
+) -> Option<PlayerCache> {
     let db_result: Vec<PlayerCache> =
         Database::get_collection_with_filter(Some(doc! { "user_id": user_id as i64 }))
             .await
@@ -18,7 +22,7 @@ pub async fn get_player_cache(user_id: u64, api: &TornAPI) -> Option<PlayerCache
     }
 
     let resp = match api
-        .get_player_basic(UserDiscordPathId::UserId(UserId::new(user_id as i32)))
+        .get_player_basic(UserDiscordPathId::UserId(UserId::new(user_id as i32)), priority)
         .await
     {
         Ok(r) => r,
@@ -1,3 +1,8 @@
+pub mod contract_edit;
+pub mod contract_events;
+pub mod contract_lifecycle;
+pub mod contract_scope;
+pub mod get_faction_cache;
 pub mod get_player_cache;
 pub mod resolve_discord_verification;
 pub mod settle_money;
@@ -2,13 +2,19 @@ use std::sync::Arc;
 
 use mongodb::bson::doc;
 use crate::database::Database;
+use crate::bot::tools::get_faction_cache::get_faction_cache;
 use crate::database::structures::Verification;
-use crate::torn_api::TornAPI;
+use crate::torn_api::{Priority, TornAPI, TornError};
 use torn_api::models::{DiscordId, UserDiscordPathId};
 
 /// Resolve a Discord user (by their Discord snowflake id) to a cached [`Verification`],
 /// fetching from the Torn API and persisting it when not yet present.
-pub async fn resolve_discord_verification(discord_id: u64, api: Arc<TornAPI>) -> Option<Verification> {
+///
+/// `Ok(None)` means the user is not verified; an error is only returned when Torn is unavailable.
+pub async fn resolve_discord_verification(
+    discord_id: u64,
+    api: Arc<TornAPI>,
+) -> Result<Option<Verification>, TornError> {
     let filter = doc! { "discord_id": discord_id as i64 };
 
     let result = Database::get_collection_with_filter::<Verification>(Some(filter))
@@ -17,25 +23,29 @@ pub async fn resolve_discord_verification(discord_id: u64, api: Arc<TornAPI>) ->
         .pop();
 
     if let Some(record) = result {
-        return Some(record);
+        return Ok(Some(record));
     }
 
     // Not cached — look the Discord user up via Torn and persist the verification.
     let profile = match api
-        .get_player_profile(UserDiscordPathId::DiscordId(DiscordId::new(discord_id.to_string())))
+        .get_player_profile(
+            UserDiscordPathId::DiscordId(DiscordId::new(discord_id.to_string())),
+            Priority::Interactive,
+        )
         .await
     {
         Ok(resp) => resp.profile,
+        Err(e @ TornError::Unavailable(_)) => return Err(e),
         Err(e) => {
             log::info!("Failed to fetch player profile for {discord_id}: {e:#}");
-            return None;
+            return Ok(None);
         }
     };
 
     // v2 profile only exposes `faction_id`; fetch the faction name separately.
     let (faction_id, faction_name) = match profile.faction_id {
-        Some(fid) => match api.get_faction_basic(fid).await {
-            Ok(basic) => (fid.0 as u64, basic.basic.name),
+        Some(fid) => match get_faction_cache(fid.0 as u64, &api, Priority::Interactive).await {
+            Ok(faction) => (faction.faction_id, faction.name),
             Err(e) => {
                 log::info!("Failed to fetch faction {fid:?} for {discord_id}: {e:#}");
                 (0, String::new())
@@ -55,5 +65,5 @@ pub async fn resolve_discord_verification(discord_id: u64, api: Arc<TornAPI>) ->
 
     Database::insert(verification.clone()).await.unwrap();
 
-    Some(verification)
+    Ok(Some(verification))
 }
\ No newline at end of file
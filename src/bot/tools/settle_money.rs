@@ -1,37 +1,124 @@
+use crate::bot::tools::contract_scope::resolve_scope;
 use crate::database::structures::{Contract, ReviveEntry};
 use crate::database::Database;
-use crate::pricing::{classify_revive, ReviveClass};
-use mongodb::bson::{doc, Bson};
+use crate::pricing::{classify_revive, PriceBreakdown, ReviveClass, ReviveCounts};
+use crate::torn_api::{Priority, TornAPI};
+use mongodb::bson::{doc, Bson, Document};
 
-pub async fn settle_contract_money(
-    contract: &Contract,
-    reviving_faction_ids: &[u64],
-) -> anyhow::Result<u64> {
+/// Filter for stored revives made for the contract's target factions by the reviving
+/// factions during the contract, leaving out the stretches it was paused.
+fn contract_revives_filter(contract: &Contract, reviving_faction_ids: &[u64]) -> Document {
     let reviver_faction_filter: Vec<Bson> = reviving_faction_ids
         .iter()
         .map(|id| Bson::Int64(*id as i64))
         .collect();
 
-    let revives = Database::get_collection_with_filter::<ReviveEntry>(Some(doc! {
+    let target_faction_filter: Vec<Bson> = contract
+        .target_factions()
+        .iter()
+        .map(|id| Bson::Int64(*id as i64))
+        .collect();
+
+    let filter = doc! {
         "timestamp": {
             "$gte": Bson::Int64(contract.started as i64),
             "$lte": Bson::Int64(contract.ended as i64)
         },
-        "target_faction": Bson::Int64(contract.faction_id as i64),
+        "target_faction": { "$in": target_faction_filter },
         "reviver_faction": { "$in": reviver_faction_filter }
-    }))
+    };
+    match contract.pause_filter() {
+        Some(pause_filter) => doc! { "$and": [filter, pause_filter] },
+        None => filter,
+    }
+}
+
+/// Stored revives made for the contract's target factions by the reviving factions
+/// during the contract, leaving out the stretches it was paused.
+pub async fn contract_revives(
+    contract: &Contract,
+    reviving_faction_ids: &[u64],
+) -> anyhow::Result<Vec<ReviveEntry>> {
+    let filter = contract_revives_filter(contract, reviving_faction_ids);
+    let revives = Database::get_collection_with_filter::<ReviveEntry>(Some(filter)).await?;
+
+    Ok(revives)
+}
+
+/// Resets `money_made` of the contract's revives, so revives an edit moved out of the
+/// contract don't keep money from its old terms. Returns how many were reset.
+pub async fn clear_contract_money(
+    contract: &Contract,
+    reviving_faction_ids: &[u64],
+) -> anyhow::Result<u64> {
+    let filter = contract_revives_filter(contract, reviving_faction_ids);
+    let cleared = Database::update_docs::<ReviveEntry>(
+        filter,
+        doc! { "$set": { "money_made": 0_i64 } },
+    )
     .await?;
 
+    Ok(cleared)
+}
+
+/// Counted revives of the contract and their price. A running contract is priced up to
+/// now.
+pub async fn contract_cost(
+    contract: &Contract,
+    reviving_faction_ids: &[u64],
+    api: &TornAPI,
+) -> anyhow::Result<(ReviveCounts, PriceBreakdown)> {
+    let mut window = contract.clone();
+    if window.ended == 0 {
+        window.ended = chrono::Utc::now().timestamp() as u64;
+    }
+
+    let revives = contract_revives(&window, reviving_faction_ids).await?;
+    let scope = resolve_scope(contract, &revives, api, Priority::Background).await?;
+
+    let mut counts = ReviveCounts {
+        successful: 0,
+        failed_counted: 0,
+    };
+    for revive in &revives {
+        match classify_revive(revive, contract.min_chance, &scope) {
+            ReviveClass::Success => counts.successful += 1,
+            ReviveClass::FailedCounted => counts.failed_counted += 1,
+            ReviveClass::Ignored | ReviveClass::OutOfScope => {}
+        }
+    }
+
+    let price = contract.pricing_type.calculate(counts, contract.faction_cut);
+    Ok((counts, price))
+}
+
+/// What settling a contract's money stored.
+#[derive(Debug, Clone, Copy)]
+pub struct Settlement {
+    pub revives: u64,
+    /// Base amount over all revives, before the faction cut.
+    pub money: u64,
+}
+
+pub async fn settle_contract_money(
+    contract: &Contract,
+    reviving_faction_ids: &[u64],
+    api: &TornAPI,
+) -> anyhow::Result<Settlement> {
+    let revives = contract_revives(contract, reviving_faction_ids).await?;
+    let scope = resolve_scope(contract, &revives, api, Priority::Background).await?;
+
     let success_rate = contract.pricing_type.success_rate();
     let failed_rate = contract.pricing_type.failed_rate();
 
     let mut updated = 0u64;
+    let mut money = 0u64;
 
     for revive in &revives {
-        let amount = match classify_revive(revive, contract.min_chance) {
+        let amount = match classify_revive(revive, contract.min_chance, &scope) {
             ReviveClass::Success => success_rate,
             ReviveClass::FailedCounted => failed_rate,
-            ReviveClass::Ignored => 0,
+            ReviveClass::Ignored | ReviveClass::OutOfScope => 0,
         };
 
         Database::update_doc::<ReviveEntry>(
@@ -41,7 +128,11 @@ pub async fn settle_contract_money(
         .await?;
 
         updated += 1;
+        money += amount;
     }
 
-    Ok(updated)
+    Ok(Settlement {
+        revives: updated,
+        money,
+    })
 }
@@ -5,6 +5,7 @@ use mongodb::options::FindOptions;
 use mongodb::{bson, error::Result, Client, Collection};
 use once_cell::sync::Lazy;
 use serde_json::{from_str, to_string};
+use std::collections::HashSet;
 use serenity::futures::TryStreamExt;
 use tokio::sync::Mutex;
 
@@ -17,6 +18,10 @@ pub struct Database;
 static DB_CONN: Lazy<Mutex<Option<Client>>> = Lazy::new(|| Mutex::new(None));
 static CONNECTION_URL: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
 
+/// Collection of the key-value bot state behind [`Database::get_value`] and
+/// [`Database::set_value`] (sync cursors and the like).
+pub(crate) const STATE_COLLECTION: &str = "state";
+
 impl Database {
     pub async fn init(connection_url: String) -> Result<()> {
         // Store the connection URL in the static variable
@@ -33,14 +38,22 @@ impl Database {
 
     pub async fn ensure_indexes() -> Result<()> {
         use crate::database::structures::{
-            Contract, IndexSetup, PlayerCache, ReviveEntry, Verification,
+            APIKey, AttackerFaction, Contract, ContractEdit, ContractEvent, FactionCache, FactionChannel, IndexSetup, PlayerCache, ReviveBackfill, ReviveEntry,
+            Verification,
         };
 
         let client = Database::get().await.unwrap();
 
+        APIKey::ensure_indexes(&client).await?;
+        AttackerFaction::ensure_indexes(&client).await?;
         Contract::ensure_indexes(&client).await?;
+        ContractEdit::ensure_indexes(&client).await?;
+        ContractEvent::ensure_indexes(&client).await?;
+        FactionCache::ensure_indexes(&client).await?;
+        FactionChannel::ensure_indexes(&client).await?;
         PlayerCache::ensure_indexes(&client).await?;
         ReviveEntry::ensure_indexes(&client).await?;
+        ReviveBackfill::ensure_indexes(&client).await?;
         Verification::ensure_indexes(&client).await?;
 
         Ok(())
@@ -153,7 +166,9 @@ impl Database {
         Ok(size)
     }
 
-    pub async fn insert_manny<T>(documents: Vec<T>) -> Result<()>
+    /// Inserts every document, skipping ones that are already stored, and returns the
+    /// documents that were new.
+    pub async fn insert_manny<T>(documents: Vec<T>) -> Result<Vec<T>>
     where
         T: CollectionName + serde::Serialize + Unpin + 'static + DatabaseName + Sync + Send,
     {
@@ -168,7 +183,7 @@ impl Database {
         let collection = db.collection::<T>(&collection_name);
 
         match collection
-            .insert_many(documents)
+            .insert_many(documents.iter())
             .with_options(
                 mongodb::options::InsertManyOptions::builder()
                     .ordered(false) // If one fails, continue with the rest
@@ -176,7 +191,7 @@ impl Database {
             )
             .await
         {
-            Ok(_) => Ok(()),
+            Ok(_) => Ok(documents),
             Err(e) => {
                 if let ErrorKind::InsertMany(ref insert_error) = *e.kind {
                     if let Some(write_errors) = &insert_error.write_errors {
@@ -188,7 +203,14 @@ impl Database {
                                 T::database_name(),
                                 T::collection_name()
                             );
-                            return Ok(());
+                            let duplicates: HashSet<usize> =
+                                write_errors.iter().map(|err| err.index).collect();
+                            return Ok(documents
+                                .into_iter()
+                                .enumerate()
+                                .filter(|(i, _)| !duplicates.contains(i))
+                                .map(|(_, document)| document)
+                                .collect());
                         }
 
                         // Only warn if there are non-duplicate errors mixed in
@@ -266,13 +288,37 @@ impl Database {
         Ok(())
     }
 
+    /// Applies `update` to every document matching `filter`, returning how many changed.
+    pub async fn update_docs<T>(filter: Document, update: Document) -> Result<u64>
+    where
+        T: CollectionName + DatabaseName + Sync + Send,
+    {
+        let client = Database::get().await.unwrap();
+        let db = client.database(T::database_name());
+        let collection: Collection<Document> = db.collection(T::collection_name());
+        let result = collection.update_many(filter, update).await?;
+        Ok(result.modified_count)
+    }
+
+    /// Deletes the documents matching `filter`, returning how many were removed.
+    pub async fn delete_doc<T>(filter: Document) -> Result<u64>
+    where
+        T: CollectionName + DatabaseName + Sync + Send,
+    {
+        let client = Database::get().await.unwrap();
+        let db = client.database(T::database_name());
+        let collection: Collection<Document> = db.collection(T::collection_name());
+        let result = collection.delete_many(filter).await?;
+        Ok(result.deleted_count)
+    }
+
     pub async fn set_value<T>(key: &str, value: T) -> Result<()>
     where
         T: serde::Serialize,
     {
         let client = Database::get().await.unwrap();
         let db = client.database("deathfr");
-        let collection: Collection<Document> = db.collection("secrets");
+        let collection: Collection<Document> = db.collection(STATE_COLLECTION);
 
         // Serialize the value into a string
         let serialized_value = to_string(&value).unwrap();
@@ -291,7 +337,7 @@ impl Database {
     {
         let client = Database::get().await.unwrap();
         let db = client.database("deathfr");
-        let collection: Collection<Document> = db.collection("secrets");
+        let collection: Collection<Document> = db.collection(STATE_COLLECTION);
 
         let filter = doc! { "key": key };
         let doc = collection.find_one(filter).await.unwrap();
@@ -0,0 +1,137 @@
+use std::sync::OnceLock;
+
+use anyhow::Context as _;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine as _;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::{digest, hmac};
+
+/// Encrypts Torn API keys before they are written to the database.
+///
+/// Both the encryption key and the fingerprint key are derived from the master key in
+/// the secrets file (`ENCRYPTION_KEY`, 32 random bytes in base64). Like [`Database`],
+/// one instance is installed at startup and used through static access.
+///
+/// [`Database`]: crate::database::Database
+pub struct KeyCipher {
+    aead: LessSafeKey,
+    fingerprint: hmac::Key,
+    rng: SystemRandom,
+}
+
+static CIPHER: OnceLock<KeyCipher> = OnceLock::new();
+
+/// Marks sealed values, so plaintext left over from before encryption can be told apart.
+const SEALED_PREFIX: &str = "v1:";
+
+impl KeyCipher {
+    pub fn from_base64(master_key: &str) -> anyhow::Result<Self> {
+        let master = STANDARD
+            .decode(master_key.trim())
+            .context("ENCRYPTION_KEY must be base64 (generate one with `openssl rand -base64 32`)")?;
+        if master.len() != 32 {
+            anyhow::bail!("ENCRYPTION_KEY must decode to 32 bytes, got {}", master.len());
+        }
+
+        let derive = |label: &[u8]| {
+            let mut ctx = digest::Context::new(&digest::SHA256);
+            ctx.update(label);
+            ctx.update(&master);
+            ctx.finish()
+        };
+
+        let aead_key = UnboundKey::new(&AES_256_GCM, derive(b"deathfr api key encryption").as_ref())
+            .map_err(|_| anyhow::anyhow!("Failed to build the API key cipher"))?;
+
+        Ok(Self {
+            aead: LessSafeKey::new(aead_key),
+            fingerprint: hmac::Key::new(
+                hmac::HMAC_SHA256,
+                derive(b"deathfr api key fingerprint").as_ref(),
+            ),
+            rng: SystemRandom::new(),
+        })
+    }
+
+    /// Makes the cipher available through [`KeyCipher::get`]. Only the first call has an effect.
+    pub fn install(self) {
+        if CIPHER.set(self).is_err() {
+            log::warn!("API key cipher was already installed");
+        }
+    }
+
+    pub fn get() -> &'static KeyCipher {
+        CIPHER.get().expect("KeyCipher::install must be called at startup")
+    }
+
+    pub fn is_sealed(value: &str) -> bool {
+        value.starts_with(SEALED_PREFIX)
+    }
+
+    pub fn seal(&self, plaintext: &str) -> String {
+        let mut nonce = [0u8; NONCE_LEN];
+        self.rng
+            .fill(&mut nonce)
+            .expect("system random number generator failed");
+
+        let mut buffer = plaintext.as_bytes().to_vec();
+        self.aead
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut buffer)
+            .expect("AES-GCM encryption of an API key failed");
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&buffer);
+        format!("{SEALED_PREFIX}{}", STANDARD.encode(sealed))
+    }
+
+    pub fn open(&self, sealed: &str) -> anyhow::Result<String> {
+        let encoded = sealed
+            .strip_prefix(SEALED_PREFIX)
+            .context("Value is not an encrypted API key")?;
+        let mut bytes = STANDARD
+            .decode(encoded)
+            .context("Encrypted API key is not valid base64")?;
+        if bytes.len() < NONCE_LEN {
+            anyhow::bail!("Encrypted API key is truncated");
+        }
+
+        let mut ciphertext = bytes.split_off(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(&bytes)
+            .map_err(|_| anyhow::anyhow!("Encrypted API key has an invalid nonce"))?;
+        let plaintext = self
+            .aead
+            .open_in_place(nonce, Aad::empty(), &mut ciphertext)
+            .map_err(|_| anyhow::anyhow!("Failed to decrypt API key (wrong ENCRYPTION_KEY?)"))?;
+
+        String::from_utf8(plaintext.to_vec()).context("Decrypted API key is not UTF-8")
+    }
+
+    /// Opens `value` if it is sealed, otherwise returns it as-is.
+    pub fn open_or_plain(&self, value: &str) -> anyhow::Result<String> {
+        if Self::is_sealed(value) {
+            self.open(value)
+        } else {
+            Ok(value.to_string())
+        }
+    }
+
+    /// Stable keyed hash of a key, to find an already stored key without decrypting every one.
+    pub fn fingerprint(&self, plaintext: &str) -> String {
+        URL_SAFE_NO_PAD.encode(hmac::sign(&self.fingerprint, plaintext.as_bytes()))
+    }
+}
+
+/// Shows enough of a key for its owner to recognise it.
+pub fn key_hint(key: &str) -> String {
+    let start: String = key.chars().take(4).collect();
+    let end: String = key
+        .chars()
+        .rev()
+        .take(2)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+    format!("{start}…{end}")
+}
@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+
+use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{doc, Document};
+use mongodb::Collection;
+use serenity::futures::TryStreamExt;
+
+use crate::database::database::STATE_COLLECTION;
+use crate::database::encryption::{key_hint, KeyCipher};
+use crate::database::structures::{APIKey, CollectionName, DatabaseName};
+use crate::database::Database;
+
+/// Brings documents written by older versions up to date. Every step is a no-op once
+/// applied, so this runs on every start (before indexes are ensured).
+pub async fn run() -> anyhow::Result<()> {
+    move_state_out_of_secrets().await?;
+    encrypt_api_keys().await?;
+    Ok(())
+}
+
+/// Bot state (revive cursors, last update) used to be kept in a collection named
+/// `secrets` although no secrets are stored there. Values are copied to the state
+/// collection, never overwriting one already written there, and `secrets` is dropped.
+async fn move_state_out_of_secrets() -> anyhow::Result<()> {
+    let client = Database::get().await.unwrap();
+    let db = client.database("deathfr");
+    if !db.list_collection_names().await?.iter().any(|name| name == "secrets") {
+        return Ok(());
+    }
+
+    let old: Collection<Document> = db.collection("secrets");
+    let state: Collection<Document> = db.collection(STATE_COLLECTION);
+    let values: Vec<Document> = old.find(doc! {}).await?.try_collect().await?;
+
+    for value in &values {
+        let (Ok(key), Some(stored)) = (value.get_str("key"), value.get("value")) else {
+            continue;
+        };
+        state
+            .update_one(
+                doc! { "key": key },
+                doc! { "$setOnInsert": { "value": stored.clone() } },
+            )
+            .upsert(true)
+            .await?;
+    }
+
+    old.drop().await?;
+    log::info!(
+        "Migration: moved {} value(s) from 'secrets' to '{STATE_COLLECTION}'",
+        values.len()
+    );
+    Ok(())
+}
+
+/// Seals plaintext API keys and drops documents that stored the same key twice. Of
+/// the copies of a key, the valid and most recently stored one is kept, so a revoked
+/// copy never wins over a working one.
+async fn encrypt_api_keys() -> anyhow::Result<()> {
+    let client = Database::get().await.unwrap();
+    let collection: Collection<Document> = client
+        .database(APIKey::database_name())
+        .collection(APIKey::collection_name());
+    let cipher = KeyCipher::get();
+
+    let legacy: Vec<Document> = collection
+        .find(doc! { "key_hash": { "$exists": false } })
+        .await?
+        .try_collect()
+        .await?;
+    if legacy.is_empty() {
+        return Ok(());
+    }
+
+    // Copies of each key, legacy and already encrypted, keyed by fingerprint.
+    let mut copies: HashMap<String, Vec<StoredCopy>> = HashMap::new();
+    for document in legacy {
+        let stored = document.get_str("api_key").unwrap_or_default();
+        let key = cipher.open_or_plain(stored)?;
+        let hash = cipher.fingerprint(&key);
+        copies
+            .entry(hash)
+            .or_default()
+            .push(StoredCopy::new(&document, Some(key))?);
+    }
+    let hashed: Vec<Document> = collection
+        .find(doc! { "key_hash": { "$in": copies.keys().cloned().collect::<Vec<_>>() } })
+        .await?
+        .try_collect()
+        .await?;
+    for document in hashed {
+        let hash = document.get_str("key_hash")?.to_string();
+        copies
+            .entry(hash)
+            .or_default()
+            .push(StoredCopy::new(&document, None)?);
+    }
+
+    let mut encrypted = 0;
+    let mut duplicates = 0;
+
+    for (hash, mut copies) in copies {
+        copies.sort_by_key(|copy| std::cmp::Reverse((copy.valid, copy.id.timestamp())));
+        let mut copies = copies.into_iter();
+        let Some(keep) = copies.next() else {
+            continue;
+        };
+
+        for duplicate in copies {
+            collection.delete_one(doc! { "_id": duplicate.id }).await?;
+            duplicates += 1;
+        }
+
+        // A kept copy without a plaintext key was encrypted already.
+        let Some(key) = keep.plain_key else {
+            continue;
+        };
+        collection
+            .update_one(
+                doc! { "_id": keep.id },
+                doc! { "$set": {
+                    "api_key": cipher.seal(&key),
+                    "key_hash": hash,
+                    "key_hint": key_hint(&key),
+                } },
+            )
+            .await?;
+        encrypted += 1;
+    }
+
+    if encrypted > 0 || duplicates > 0 {
+        log::info!(
+            "Migration: encrypted {encrypted} API key(s), removed {duplicates} duplicate(s)"
+        );
+    }
+    Ok(())
+}
+
+/// One stored document of a key, as far as choosing which copy to keep goes.
+struct StoredCopy {
+    id: ObjectId,
+    valid: bool,
+    /// The key in plain text, for documents that still need encrypting.
+    plain_key: Option<String>,
+}
+
+impl StoredCopy {
+    fn new(document: &Document, plain_key: Option<String>) -> anyhow::Result<Self> {
+        Ok(Self {
+            id: document.get_object_id("_id")?,
+            valid: document.get_bool("valid").unwrap_or(false),
+            plain_key,
+        })
+    }
+}
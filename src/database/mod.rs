@@ -1,4 +1,6 @@
 mod database;
+pub mod encryption;
+pub mod migrations;
 pub mod structures;
 
 pub use database::Database;
@@ -1,4 +1,6 @@
+use crate::database::encryption::{key_hint, KeyCipher};
 use crate::database::structures::{CollectionName, DatabaseName};
+use crate::torn_api::KeyAccess;
 use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
 
@@ -6,11 +8,78 @@ use serde::{Deserialize, Serialize};
 pub struct APIKey {
     #[serde(rename = "_id")] // Ensure it maps to MongoDB's "_id" field
     pub(crate) id: ObjectId,
+    /// The key, sealed with [`KeyCipher`]. Use [`APIKey::key`] to read it.
     #[serde(default)]
     pub(crate) api_key: String,
+    /// [`KeyCipher::fingerprint`] of the key, to find resubmissions without decrypting.
+    #[serde(default)]
+    pub(crate) key_hash: String,
+    /// First and last characters of the key, for donors to tell their keys apart.
+    #[serde(default)]
+    pub(crate) key_hint: String,
     #[serde(default)]
     pub(crate) name: String,
+    /// Torn player id of the key owner, used to share one rate limit across their keys.
+    #[serde(default)]
+    pub(crate) owner_id: Option<u64>,
     pub(crate) valid: bool,
+    /// Discord user that submitted the key, so they can be told when Torn rejects it.
+    /// Missing on keys donated before this was tracked.
+    #[serde(default)]
+    pub(crate) discord_id: Option<u64>,
+    /// Torn error code that got the key removed from the rotation.
+    #[serde(default)]
+    pub(crate) error_code: Option<u16>,
+    #[serde(default)]
+    pub(crate) error_message: Option<String>,
+    /// Unix timestamp of when the key was marked invalid.
+    #[serde(default)]
+    pub(crate) invalidated_at: Option<u64>,
+    /// Access level and selections of the key, as reported by Torn. Missing until
+    /// the key has been inspected.
+    #[serde(default)]
+    pub(crate) access: Option<KeyAccess>,
+    /// Requests per minute the donor allows; [`APIKey::DEFAULT_RATE_LIMIT`] when unset.
+    #[serde(default)]
+    pub(crate) rate_limit: Option<u32>,
+    /// Requests made with the key, kept across restarts. The rotation adds to it every
+    /// few minutes, so the last few minutes are only counted in memory.
+    #[serde(default)]
+    pub(crate) requests: u64,
+}
+
+impl APIKey {
+    pub const DEFAULT_RATE_LIMIT: u32 = 10;
+
+    /// A newly submitted, valid key. The plaintext key is only kept sealed.
+    pub fn new(key: &str, name: String, owner_id: u64, discord_id: u64, access: KeyAccess) -> Self {
+        let cipher = KeyCipher::get();
+        Self {
+            id: ObjectId::new(),
+            api_key: cipher.seal(key),
+            key_hash: cipher.fingerprint(key),
+            key_hint: key_hint(key),
+            name,
+            owner_id: Some(owner_id),
+            valid: true,
+            discord_id: Some(discord_id),
+            error_code: None,
+            error_message: None,
+            invalidated_at: None,
+            access: Some(access),
+            rate_limit: None,
+            requests: 0,
+        }
+    }
+
+    /// Decrypts the stored key.
+    pub fn key(&self) -> anyhow::Result<String> {
+        KeyCipher::get().open(&self.api_key)
+    }
+
+    pub fn rate_limit(&self) -> u32 {
+        self.rate_limit.unwrap_or(Self::DEFAULT_RATE_LIMIT)
+    }
 }
 
 impl CollectionName for APIKey {
@@ -24,3 +93,25 @@ impl DatabaseName for APIKey {
         "deathfr"
     }
 }
+
+#[async_trait::async_trait]
+impl crate::database::structures::IndexSetup for APIKey {
+    async fn ensure_indexes(client: &mongodb::Client) -> mongodb::error::Result<()> {
+        let db = client.database(Self::database_name());
+        let collection = db.collection::<APIKey>(Self::collection_name());
+
+        // One document per key; sparse so documents from before encryption don't collide.
+        let unique_model = mongodb::IndexModel::builder()
+            .keys(mongodb::bson::doc! { "key_hash": 1 })
+            .options(
+                mongodb::options::IndexOptions::builder()
+                    .unique(true)
+                    .sparse(true)
+                    .build(),
+            )
+            .build();
+
+        collection.create_index(unique_model).await?;
+        Ok(())
+    }
+}
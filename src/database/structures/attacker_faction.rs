@@ -0,0 +1,57 @@
+use crate::database::structures::{CollectionName, DatabaseName};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Faction an attacker was in when first looked up for a defensive contract scope.
+///
+/// Lookups happen as revives come in (revive log, budget watch), so the stored faction
+/// is the one the attacker had around the time of the revive, and settling the contract
+/// later neither spends key quota on it again nor picks up a faction they joined since.
+/// Expires after 30 days, longer than contracts run.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AttackerFaction {
+    pub(crate) user_id: u64,
+    /// `None` when the attacker was in no faction.
+    pub(crate) faction_id: Option<u64>,
+
+    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub(crate) expire_at: DateTime<Utc>,
+}
+
+impl DatabaseName for AttackerFaction {
+    fn database_name() -> &'static str {
+        "deathfr"
+    }
+}
+
+impl CollectionName for AttackerFaction {
+    fn collection_name() -> &'static str {
+        "attacker_factions"
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::database::structures::IndexSetup for AttackerFaction {
+    async fn ensure_indexes(client: &mongodb::Client) -> mongodb::error::Result<()> {
+        let db = client.database(Self::database_name());
+        let collection = db.collection::<AttackerFaction>(Self::collection_name());
+
+        let unique_model = mongodb::IndexModel::builder()
+            .keys(mongodb::bson::doc! { "user_id": 1 })
+            .options(mongodb::options::IndexOptions::builder().unique(true).build())
+            .build();
+
+        let ttl_model = mongodb::IndexModel::builder()
+            .keys(mongodb::bson::doc! { "expire_at": 1 })
+            .options(
+                mongodb::options::IndexOptions::builder()
+                    .expire_after(std::time::Duration::from_secs(0))
+                    .build(),
+            )
+            .build();
+
+        collection.create_index(unique_model).await?;
+        collection.create_index(ttl_model).await?;
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 use crate::database::structures::{CollectionName, DatabaseName};
-use crate::pricing::PricingType;
+use crate::pricing::{ContractScope, PricingType};
 use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{doc, Bson, Document};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -20,10 +21,21 @@ pub struct Contract {
     pub(crate) id: Option<ObjectId>,
     pub(crate) contract_id: String,
     pub(crate) contract_name: String,
+    /// Main target faction; the first of `faction_ids`.
     pub(crate) faction_id: u64,
+    /// Every faction whose revives the contract covers. Contracts from before a contract
+    /// could cover several factions have none stored and cover only `faction_id`.
+    #[serde(default)]
+    pub(crate) faction_ids: Vec<u64>,
     pub(crate) min_chance: u64,
+    /// Which revives count, by what put the target in hospital. Older contracts count all.
+    #[serde(default)]
+    pub(crate) scope: ContractScope,
     pub(crate) started: u64,
     pub(crate) ended: u64,
+    /// When the scheduler ends the contract, if it was given an end time.
+    #[serde(default)]
+    pub(crate) end_time: Option<u64>,
     pub(crate) status: Status,
     pub(crate) faction_cut: i64,
     #[serde(default)]
@@ -32,8 +44,71 @@ pub struct Contract {
     pub(crate) revives_synced: bool,
     #[serde(default)]
     pub(crate) money_settled: bool,
+    /// Most the client wants to spend, as a final price including the faction cut.
+    #[serde(default)]
+    pub(crate) budget: Option<u64>,
+    /// End the contract once its running cost reaches `budget`.
+    #[serde(default)]
+    pub(crate) end_at_budget: bool,
+    /// Highest budget alert threshold (in percent) already sent, so each is sent once.
+    #[serde(default)]
+    pub(crate) budget_alerted: u64,
+    /// Stretches inside the contract where coverage was paused, oldest first.
+    #[serde(default)]
+    pub(crate) pauses: Vec<Pause>,
+}
+
+/// A paused stretch of a contract; revives during it don't count.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Pause {
+    pub(crate) from: u64,
+    /// `None` while the contract is still paused.
+    pub(crate) to: Option<u64>,
 }
 
+impl Contract {
+    /// Factions whose revives count for this contract.
+    pub fn target_factions(&self) -> Vec<u64> {
+        if self.faction_ids.is_empty() {
+            vec![self.faction_id]
+        } else {
+            self.faction_ids.clone()
+        }
+    }
+
+    /// Whether coverage is paused right now.
+    pub fn is_paused(&self) -> bool {
+        self.pauses.last().is_some_and(|pause| pause.to.is_none())
+    }
+
+    /// Whether `timestamp` falls inside a pause.
+    pub fn is_paused_at(&self, timestamp: u64) -> bool {
+        self.pauses
+            .iter()
+            .any(|pause| pause.from <= timestamp && pause.to.is_none_or(|to| timestamp <= to))
+    }
+
+    /// Filter on revive `timestamp` that leaves out the paused stretches, to combine
+    /// with a revive query under `$and`. `None` when the contract was never paused.
+    pub fn pause_filter(&self) -> Option<Document> {
+        if self.pauses.is_empty() {
+            return None;
+        }
+
+        let paused: Vec<Bson> = self
+            .pauses
+            .iter()
+            .map(|pause| {
+                let mut range = doc! { "$gte": pause.from as i64 };
+                if let Some(to) = pause.to {
+                    range.insert("$lte", to as i64);
+                }
+                Bson::Document(doc! { "timestamp": range })
+            })
+            .collect();
+        Some(doc! { "$nor": paused })
+    }
+}
 
 impl CollectionName for Contract {
     fn collection_name() -> &'static str {
@@ -61,3 +136,82 @@ impl crate::database::structures::IndexSetup for Contract {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contract(pauses: Vec<Pause>) -> Contract {
+        Contract {
+            id: None,
+            contract_id: "abc123".to_string(),
+            contract_name: "Test".to_string(),
+            faction_id: 1,
+            faction_ids: vec![1],
+            min_chance: 50,
+            scope: ContractScope::All,
+            started: 1_000,
+            ended: 0,
+            end_time: None,
+            status: Status::Active,
+            faction_cut: 10,
+            pricing_type: PricingType::Legacy,
+            revives_synced: false,
+            money_settled: false,
+            budget: None,
+            end_at_budget: false,
+            budget_alerted: 0,
+            pauses,
+        }
+    }
+
+    #[test]
+    fn pause_boundaries_are_inclusive() {
+        let contract = contract(vec![
+            Pause {
+                from: 2_000,
+                to: Some(3_000),
+            },
+            Pause { from: 5_000, to: None },
+        ]);
+
+        assert!(!contract.is_paused_at(1_999));
+        assert!(contract.is_paused_at(2_000));
+        assert!(contract.is_paused_at(3_000));
+        assert!(!contract.is_paused_at(3_001));
+        assert!(!contract.is_paused_at(4_999));
+        assert!(contract.is_paused_at(5_000));
+        assert!(contract.is_paused_at(u64::MAX));
+        assert!(contract.is_paused());
+    }
+
+    #[test]
+    fn pause_filter_excludes_closed_and_open_pauses() {
+        let contract = contract(vec![
+            Pause {
+                from: 2_000,
+                to: Some(3_000),
+            },
+            Pause { from: 5_000, to: None },
+        ]);
+
+        assert_eq!(
+            contract.pause_filter(),
+            Some(doc! {
+                "$nor": [
+                    { "timestamp": { "$gte": 2_000_i64, "$lte": 3_000_i64 } },
+                    { "timestamp": { "$gte": 5_000_i64 } },
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn never_paused_contract_has_no_pause_filter() {
+        let contract = contract(Vec::new());
+
+        assert_eq!(contract.pause_filter(), None);
+        assert!(!contract.is_paused_at(2_000));
+        assert!(!contract.is_paused());
+    }
+}
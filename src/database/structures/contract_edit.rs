@@ -0,0 +1,41 @@
+use crate::database::structures::{CollectionName, DatabaseName};
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+
+/// One changed term of a contract, stored by `/contract edit` and the wizard's edit mode.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ContractEdit {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub(crate) id: Option<ObjectId>,
+    pub(crate) contract_id: String,
+    /// Name of the `Contract` field, e.g. `min_chance`.
+    pub(crate) field: String,
+    pub(crate) old_value: String,
+    pub(crate) new_value: String,
+    /// Discord user id of the admin who made the change.
+    pub(crate) edited_by: u64,
+    pub(crate) edited_at: u64,
+}
+
+impl DatabaseName for ContractEdit {}
+
+impl CollectionName for ContractEdit {
+    fn collection_name() -> &'static str {
+        "contract_edits"
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::database::structures::IndexSetup for ContractEdit {
+    async fn ensure_indexes(client: &mongodb::Client) -> mongodb::error::Result<()> {
+        let db = client.database(Self::database_name());
+        let collection = db.collection::<ContractEdit>(Self::collection_name());
+
+        let model = mongodb::IndexModel::builder()
+            .keys(mongodb::bson::doc! { "contract_id": 1, "edited_at": 1 })
+            .build();
+
+        collection.create_index(model).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,86 @@
+use crate::database::structures::{CollectionName, DatabaseName};
+use mongodb::bson::oid::ObjectId;
+use mongodb::bson::Document;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ContractEventKind {
+    /// Created with `/contract start`.
+    Created,
+    /// Created by confirming `/start-contract-interactive`.
+    WizardConfirmed,
+    /// A pending contract reached its start time.
+    Started,
+    /// Not stored: `/contract history` shows the rows of `contract_edits` as these.
+    Edited,
+    Paused,
+    Resumed,
+    /// The running cost passed a budget alert threshold.
+    BudgetAlert,
+    Ended,
+    /// Money of the contract's revives was settled when it ended.
+    Settled,
+    /// Money was settled later, when another contract ended, after a backfill or an
+    /// edit marked the contract unsettled.
+    BackfillSettled,
+    /// A revive backfill stored revives inside the contract and marked it for settling again.
+    BackfillReset,
+    ReportGenerated,
+}
+
+impl ContractEventKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Created => "Created",
+            Self::WizardConfirmed => "Created (wizard)",
+            Self::Started => "Started",
+            Self::Edited => "Edited",
+            Self::Paused => "Paused",
+            Self::Resumed => "Resumed",
+            Self::BudgetAlert => "Budget alert",
+            Self::Ended => "Ended",
+            Self::Settled => "Settled",
+            Self::BackfillSettled => "Settled again",
+            Self::BackfillReset => "Marked for resettlement",
+            Self::ReportGenerated => "Report generated",
+        }
+    }
+}
+
+/// One lifecycle action on a contract, shown by `/contract history`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ContractEvent {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub(crate) id: Option<ObjectId>,
+    pub(crate) contract_id: String,
+    pub(crate) kind: ContractEventKind,
+    /// Discord user id of whoever caused it; `None` when Deathfr did it on its own.
+    pub(crate) actor: Option<u64>,
+    pub(crate) timestamp: u64,
+    /// Details of the action, e.g. the terms a contract was created with.
+    pub(crate) payload: Document,
+}
+
+impl DatabaseName for ContractEvent {}
+
+impl CollectionName for ContractEvent {
+    fn collection_name() -> &'static str {
+        "contract_events"
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::database::structures::IndexSetup for ContractEvent {
+    async fn ensure_indexes(client: &mongodb::Client) -> mongodb::error::Result<()> {
+        let db = client.database(Self::database_name());
+        let collection = db.collection::<ContractEvent>(Self::collection_name());
+
+        let model = mongodb::IndexModel::builder()
+            .keys(mongodb::bson::doc! { "contract_id": 1, "timestamp": 1 })
+            .build();
+
+        collection.create_index(model).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,54 @@
+use crate::database::structures::{CollectionName, DatabaseName};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Cached faction name, so contract and report commands don't spend key quota on
+/// lookups of factions they have already seen. Expires after a day to pick up renames.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FactionCache {
+    pub(crate) faction_id: u64,
+    pub(crate) name: String,
+
+    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub(crate) expire_at: DateTime<Utc>,
+}
+
+impl DatabaseName for FactionCache {
+    fn database_name() -> &'static str {
+        "deathfr"
+    }
+}
+
+impl CollectionName for FactionCache {
+    fn collection_name() -> &'static str {
+        "faction_cache"
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::database::structures::IndexSetup for FactionCache {
+    async fn ensure_indexes(client: &mongodb::Client) -> mongodb::error::Result<()> {
+        let db = client.database(Self::database_name());
+        let collection = db.collection::<FactionCache>(Self::collection_name());
+
+        // Unique index on faction_id
+        let unique_model = mongodb::IndexModel::builder()
+            .keys(mongodb::bson::doc! { "faction_id": 1 })
+            .options(mongodb::options::IndexOptions::builder().unique(true).build())
+            .build();
+
+        // TTL index on expire_at
+        let ttl_model = mongodb::IndexModel::builder()
+            .keys(mongodb::bson::doc! { "expire_at": 1 })
+            .options(
+                mongodb::options::IndexOptions::builder()
+                    .expire_after(std::time::Duration::from_secs(0))
+                    .build(),
+            )
+            .build();
+
+        collection.create_index(unique_model).await?;
+        collection.create_index(ttl_model).await?;
+        Ok(())
+    }
+}
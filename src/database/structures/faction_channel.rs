@@ -0,0 +1,34 @@
+use crate::database::structures::{CollectionName, DatabaseName};
+use serde::{Deserialize, Serialize};
+
+/// Discord channel of a client faction, where it is told when its contracts start and
+/// end. Set with `/contract link`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FactionChannel {
+    pub(crate) faction_id: u64,
+    pub(crate) channel_id: u64,
+}
+
+impl DatabaseName for FactionChannel {}
+
+impl CollectionName for FactionChannel {
+    fn collection_name() -> &'static str {
+        "faction_channels"
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::database::structures::IndexSetup for FactionChannel {
+    async fn ensure_indexes(client: &mongodb::Client) -> mongodb::error::Result<()> {
+        let db = client.database(Self::database_name());
+        let collection = db.collection::<FactionChannel>(Self::collection_name());
+
+        let model = mongodb::IndexModel::builder()
+            .keys(mongodb::bson::doc! { "faction_id": 1 })
+            .options(mongodb::options::IndexOptions::builder().unique(true).build())
+            .build();
+
+        collection.create_index(model).await?;
+        Ok(())
+    }
+}
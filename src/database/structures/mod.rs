@@ -1,16 +1,29 @@
 mod api_key;
+mod attacker_faction;
 mod colection_name;
 mod contract;
+mod contract_edit;
+mod contract_event;
 mod database_name;
+mod faction_cache;
+mod faction_channel;
 mod player_cache;
 mod revive;
+mod revive_backfill;
 mod verification;
 
 pub use api_key::APIKey;
+pub use attacker_faction::AttackerFaction;
 pub use contract::Contract;
+pub use contract::Pause;
 pub use contract::Status;
+pub use contract_edit::ContractEdit;
+pub use contract_event::{ContractEvent, ContractEventKind};
+pub use faction_cache::FactionCache;
+pub use faction_channel::FactionChannel;
 pub use player_cache::PlayerCache;
 pub use revive::ReviveEntry;
+pub use revive_backfill::{BackfillStatus, ReviveBackfill};
 pub use verification::Verification;
 
 pub use colection_name::CollectionName;
@@ -0,0 +1,77 @@
+use crate::database::structures::{CollectionName, DatabaseName};
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum BackfillStatus {
+    #[serde(rename = "running")]
+    Running,
+    #[serde(rename = "done")]
+    Done,
+    #[serde(rename = "failed")]
+    Failed,
+}
+
+/// An import of a faction's older revives started with `/revives backfill`.
+///
+/// The cursor is stored after every page, so a running import continues where it
+/// stopped when the bot restarts.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReviveBackfill {
+    #[serde(rename = "_id")]
+    pub(crate) id: ObjectId,
+    pub(crate) faction_id: u64,
+    /// Start of the imported window, unix time.
+    pub(crate) from: u64,
+    /// End of the imported window, unix time.
+    pub(crate) to: u64,
+    /// Revives before this are imported.
+    pub(crate) cursor: u64,
+    pub(crate) inserted: u64,
+    pub(crate) status: BackfillStatus,
+    #[serde(default)]
+    pub(crate) error: Option<String>,
+    /// Whether the faction's sync cursor skipped the window for this import, so its
+    /// revives from `cursor` to `to` are only complete once the import finishes.
+    #[serde(default)]
+    pub(crate) gap_open: bool,
+    pub(crate) requested_by: u64,
+    /// Message edited with the progress of the import.
+    pub(crate) channel_id: u64,
+    pub(crate) message_id: u64,
+}
+
+impl CollectionName for ReviveBackfill {
+    fn collection_name() -> &'static str {
+        "revive_backfills"
+    }
+}
+
+impl DatabaseName for ReviveBackfill {}
+
+#[async_trait::async_trait]
+impl crate::database::structures::IndexSetup for ReviveBackfill {
+    async fn ensure_indexes(client: &mongodb::Client) -> mongodb::error::Result<()> {
+        let db = client.database(Self::database_name());
+        let collection = db.collection::<ReviveBackfill>(Self::collection_name());
+
+        let model = mongodb::IndexModel::builder()
+            .keys(mongodb::bson::doc! { "status": 1 })
+            .build();
+
+        // One running import per faction.
+        let running_model = mongodb::IndexModel::builder()
+            .keys(mongodb::bson::doc! { "faction_id": 1, "status": 1 })
+            .options(
+                mongodb::options::IndexOptions::builder()
+                    .unique(true)
+                    .partial_filter_expression(mongodb::bson::doc! { "status": "running" })
+                    .build(),
+            )
+            .build();
+
+        collection.create_index(model).await?;
+        collection.create_index(running_model).await?;
+        Ok(())
+    }
+}
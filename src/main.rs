@@ -1,31 +1,52 @@
 mod bot;
 mod database;
 mod pricing;
+mod shutdown;
 mod torn_api;
 
 use crate::bot::commands;
-use crate::bot::handler::event_handler;
+use crate::bot::handler::{command_check, event_handler, on_error, post_command, pre_command};
 use crate::bot::{Data, Secrets};
 use crate::database::structures::APIKey;
 use crate::database::Database;
+use crate::shutdown::Shutdown;
+use anyhow::Context as _;
 use log;
-use serenity::all::GuildId;
+use serenity::all::{ChannelId, GuildId};
 use serenity::prelude::*;
 use std::env;
+use std::time::Duration;
 
-use crate::torn_api::{ReviveMonitor, ReviveSourceConfig, TornAPI};
+use crate::torn_api::{KeyAccess, Replay, ReviveMonitor, ReviveSourceConfig, TornAPI};
 use std::sync::Arc;
 
+/// How long shutdown waits for running work before disconnecting anyway.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(60);
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn,deathfr=info")).init();
 
     let loaded = Secrets::load()?;
 
+    // `--seal-key <key>` prints the encrypted form of a key for REVIVE_FACTIONS and exits.
+    let args: Vec<String> = env::args().collect();
+    if let Some(i) = args.iter().position(|a| a == "--seal-key") {
+        let key = args.get(i + 1).context("Expected a Torn API key after '--seal-key'")?;
+        println!("{}", loaded.cipher.seal(key));
+        return Ok(());
+    }
+
+    loaded.cipher.install();
+
     Database::init(loaded.database_url.clone())
         .await
         .expect("Error initializing database");
 
+    database::migrations::run()
+        .await
+        .expect("Error migrating database");
+
     Database::ensure_indexes()
         .await
         .expect("Error ensuring database indexes");
@@ -36,10 +57,20 @@ async fn main() -> anyhow::Result<()> {
         .into_iter()
         .filter_map(|key| {
             if key.valid {
+                let plain = match key.key() {
+                    Ok(plain) => plain,
+                    Err(e) => {
+                        log::error!("Skipping API key of {}: {e:#}", key.name);
+                        return None;
+                    }
+                };
                 Some(torn_api::APIKey {
-                    key: key.api_key,
-                    rate_limit: 10,
+                    rate_limit: key.rate_limit(),
+                    key: plain,
                     owner: key.name,
+                    owner_id: key.owner_id,
+                    db_id: Some(key.id),
+                    access: key.access.unwrap_or_default(),
                 })
             } else {
                 None
@@ -55,10 +86,31 @@ async fn main() -> anyhow::Result<()> {
             key: secret.test_api_key.clone(),
             rate_limit: 100,
             owner: "Test Key (Llyfr)".to_string(),
+            owner_id: None,
+            db_id: None,
+            access: KeyAccess::default(),
         }];
     }
 
-    let api = TornAPI::new(api_keys);
+    let replay = match &secret.torn_replay_dir {
+        Some(dir) => {
+            log::info!("Replaying Torn API responses from '{dir}'");
+            Some(Arc::new(Replay::load(dir)?))
+        }
+        None => None,
+    };
+
+    // Fixtures don't care about the key, but the rotation still needs one to hand out.
+    if replay.is_some() && api_keys.is_empty() {
+        api_keys.push(torn_api::APIKey {
+            key: "replay".to_string(),
+            rate_limit: 100,
+            owner: "Replay Key".to_string(),
+            owner_id: None,
+            db_id: None,
+            access: KeyAccess::default(),
+        });
+    }
 
     if secret.dev {
         log::info!("Running in dev mode");
@@ -73,24 +125,48 @@ async fn main() -> anyhow::Result<()> {
         secret.revive_sources.clone()
     };
 
-    let revive_monitor = Arc::new(ReviveMonitor::new(revive_sources));
-    let data = Data::new(secret.clone(), api, revive_monitor.clone());
+    // Revive keys share the rotation with donated keys; routing keeps faction-only
+    // selections on the keys of that faction.
+    api_keys.extend(revive_sources.iter().map(ReviveSourceConfig::rotation_key));
+
+    let api = match replay {
+        Some(replay) => TornAPI::new(api_keys).with_replay(replay),
+        None => TornAPI::new(api_keys),
+    };
+    let api = Arc::new(api);
+
+    // Keys loaded without known access (older donations, revive keys) are inspected once.
+    tokio::spawn({
+        let api = api.clone();
+        async move { api.learn_access().await }
+    });
+
+    let revive_monitor = Arc::new(ReviveMonitor::new(api.clone(), revive_sources));
+    let shutdown = Shutdown::new();
+    let data = Data::new(secret.clone(), api, revive_monitor.clone(), shutdown.clone());
 
     let framework = poise::Framework::builder()
         .options(poise::FrameworkOptions {
             commands: vec![
                 commands::reviveme::reviveme(),
                 commands::contract::contract(),
+                commands::revives::revives(),
                 commands::contract_wizard::start_contract_interactive(),
+                commands::contract_wizard::edit_contract_interactive(),
                 commands::stats::stats(),
                 commands::report::report(),
                 commands::submitkey::submitkey(),
+                commands::mykeys::mykeys(),
                 commands::help::help(),
                 commands::new_contract::new_contract(),
             ],
             event_handler: |ctx, event, framework, data| {
                 Box::pin(event_handler(ctx, event, framework, data))
             },
+            command_check: Some(|ctx| Box::pin(command_check(ctx))),
+            pre_command: |ctx| Box::pin(pre_command(ctx)),
+            post_command: |ctx| Box::pin(post_command(ctx)),
+            on_error: |error| Box::pin(on_error(error)),
             ..Default::default()
         })
         .setup(move |ctx, ready, _framework| {
@@ -127,9 +203,12 @@ async fn main() -> anyhow::Result<()> {
 
                 let guild_commands = poise::builtins::create_application_commands(&[
                     commands::contract::contract(),
+                    commands::revives::revives(),
                     commands::contract_wizard::start_contract_interactive(),
+                    commands::contract_wizard::edit_contract_interactive(),
                     commands::stats::stats(),
                     commands::submitkey::submitkey(),
+                    commands::mykeys::mykeys(),
                 ]);
 
                 serenity::all::Command::set_global_commands(&ctx.http, global_commands).await?;
@@ -140,10 +219,76 @@ async fn main() -> anyhow::Result<()> {
 
                 log::info!("All commands registered!");
 
+                tokio::spawn(bot::key_removals::run_notifier(
+                    ctx.http.clone(),
+                    secrets.clone(),
+                    data.torn_api.subscribe_removals(),
+                ));
+
+                if let Some(channel) = secrets.revive_log_channel {
+                    tokio::spawn(bot::revive_log::run_revive_log(
+                        ctx.http.clone(),
+                        secrets.clone(),
+                        data.torn_api.clone(),
+                        ChannelId::new(channel),
+                        data.revive_monitor.subscribe_revives(),
+                    ));
+                }
+
+                // Dev mode runs on the test key only, so stored keys must not be hot-added.
+                if !secrets.dev {
+                    tokio::spawn(
+                        data.torn_api
+                            .clone()
+                            .run_key_health(data.shutdown.clone()),
+                    );
+                }
+
+                tokio::spawn(
+                    data.torn_api
+                        .clone()
+                        .run_usage_flush(data.shutdown.clone()),
+                );
+
+                tokio::spawn(bot::monitor_alerts::run_alerts(
+                    ctx.http.clone(),
+                    secrets.clone(),
+                    data.revive_monitor.subscribe_alerts(),
+                ));
+                tokio::spawn(
+                    data.revive_monitor
+                        .clone()
+                        .run_watchdog(data.shutdown.clone()),
+                );
+
+                tokio::spawn(bot::contract_scheduler::run_scheduler(
+                    ctx.http.clone(),
+                    secrets.clone(),
+                    data.torn_api.clone(),
+                    data.revive_monitor.clone(),
+                    data.shutdown.clone(),
+                ));
+
+                tokio::spawn(bot::budget_watch::run_budget_watch(
+                    ctx.http.clone(),
+                    secrets.clone(),
+                    data.torn_api.clone(),
+                    data.revive_monitor.subscribe_revives(),
+                    data.revive_monitor.subscribe_backfills(),
+                    data.shutdown.clone(),
+                ));
+
+                tokio::spawn(bot::revive_backfill::resume_backfills(
+                    ctx.http.clone(),
+                    data.revive_monitor.clone(),
+                    data.shutdown.clone(),
+                ));
+
                 tokio::spawn({
                     let monitor = data.revive_monitor.clone();
+                    let shutdown = data.shutdown.clone();
                     async move {
-                        monitor.run_loop().await;
+                        monitor.run_loop(shutdown).await;
                     }
                 });
 
@@ -168,7 +313,28 @@ async fn main() -> anyhow::Result<()> {
         .await
         .expect("Err creating client");
 
+    // On SIGTERM/SIGINT: turn away new work, let running syncs, backfill pages and
+    // commands finish, then disconnect from Discord so `start` returns.
+    tokio::spawn({
+        let shard_manager = client.shard_manager.clone();
+        async move {
+            crate::shutdown::wait_for_signal().await;
+            log::info!("Shutdown requested, waiting for running work to finish…");
+            shutdown.trigger();
+
+            if !shutdown.wait_idle(SHUTDOWN_GRACE).await {
+                log::warn!(
+                    "Work still running after {}s, shutting down anyway",
+                    SHUTDOWN_GRACE.as_secs()
+                );
+            }
+
+            shard_manager.shutdown_all().await;
+        }
+    });
+
     client.start().await?;
     Database::close().await;
+    log::info!("Shut down cleanly");
     Ok(())
 }
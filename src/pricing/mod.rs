@@ -1,5 +1,6 @@
 use crate::database::structures::ReviveEntry;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
 #[serde(rename_all = "snake_case")]
@@ -15,6 +16,139 @@ pub enum ReviveClass {
     Success,
     FailedCounted,
     Ignored,
+    /// Outside the contract's scope, e.g. a target hospitalized while attacking under a
+    /// defensive contract.
+    OutOfScope,
+}
+
+/// Which revives of the target factions a contract pays for, judged by the hospital
+/// reason of the revived player.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ContractScope {
+    /// Every revive, whatever put the target in hospital.
+    #[default]
+    All,
+    /// Only targets hospitalized by a member of one of these factions.
+    Defensive { attacker_factions: Vec<u64> },
+    /// Only targets that lost an attack they made.
+    Offensive,
+}
+
+/// Why a revived player was in hospital, parsed from `ReviveEntry.target_hospital_reason`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HospitalCause {
+    /// Put there by an attacker. The id is known when the reason kept its profile link
+    /// and the attacker was not anonymous.
+    Attacked { attacker_id: Option<u64> },
+    /// Lost an attack the target made.
+    LostAttack,
+    /// Anything else: drugs, crimes, travel, ...
+    Other,
+}
+
+const ATTACKED_PREFIXES: [&str; 3] = ["Hospitalized by", "Attacked by", "Mugged by"];
+const LOST_ATTACK_PREFIXES: [&str; 1] = ["Lost to"];
+
+impl HospitalCause {
+    /// Parses reasons like `Hospitalized by <a href = "...profiles.php?XID=1">Name</a>`.
+    pub fn parse(reason: &str) -> Self {
+        let reason = reason.trim();
+        if ATTACKED_PREFIXES.iter().any(|prefix| reason.starts_with(prefix)) {
+            Self::Attacked {
+                attacker_id: profile_id(reason),
+            }
+        } else if LOST_ATTACK_PREFIXES.iter().any(|prefix| reason.starts_with(prefix)) {
+            Self::LostAttack
+        } else {
+            Self::Other
+        }
+    }
+}
+
+/// The `XID` of the first profile link in `text`.
+fn profile_id(text: &str) -> Option<u64> {
+    let start = text.find("XID=")? + "XID=".len();
+    let digits: String = text[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+impl ContractScope {
+    pub fn label(&self) -> String {
+        match self {
+            Self::All => "All revives".to_string(),
+            Self::Defensive { attacker_factions } => format!(
+                "Defensive against {}",
+                attacker_factions
+                    .iter()
+                    .map(|id| id.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Self::Offensive => "Offensive".to_string(),
+        }
+    }
+
+    /// Attackers whose faction has to be known to check this scope.
+    pub fn attackers_to_resolve<'a>(
+        &self,
+        revives: impl IntoIterator<Item = &'a ReviveEntry>,
+    ) -> Vec<u64> {
+        if !matches!(self, Self::Defensive { .. }) {
+            return Vec::new();
+        }
+
+        let mut attackers: Vec<u64> = revives
+            .into_iter()
+            .filter_map(|revive| match HospitalCause::parse(&revive.target_hospital_reason) {
+                HospitalCause::Attacked { attacker_id } => attacker_id,
+                _ => None,
+            })
+            .collect();
+        attackers.sort_unstable();
+        attackers.dedup();
+        attackers
+    }
+}
+
+/// A contract's scope together with the factions of the attackers it depends on.
+#[derive(Debug, Clone, Default)]
+pub struct ResolvedScope {
+    scope: ContractScope,
+    /// Attacker id to faction id; attackers without a faction are left out.
+    attacker_factions: HashMap<u64, u64>,
+}
+
+impl ResolvedScope {
+    pub fn new(scope: ContractScope, attacker_factions: HashMap<u64, u64>) -> Self {
+        Self {
+            scope,
+            attacker_factions,
+        }
+    }
+
+    /// Whether the revive falls under the contract. Under a defensive scope an attacker
+    /// whose faction can't be told (anonymous, or stored before reasons kept profile
+    /// links) is treated as outside it.
+    pub fn covers(&self, revive: &ReviveEntry) -> bool {
+        let cause = HospitalCause::parse(&revive.target_hospital_reason);
+        match &self.scope {
+            ContractScope::All => true,
+            ContractScope::Offensive => cause == HospitalCause::LostAttack,
+            ContractScope::Defensive { attacker_factions } => match cause {
+                HospitalCause::Attacked {
+                    attacker_id: Some(attacker_id),
+                } => self
+                    .attacker_factions
+                    .get(&attacker_id)
+                    .is_some_and(|faction| attacker_factions.contains(faction)),
+                _ => false,
+            },
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -76,8 +210,10 @@ impl PricingType {
     }
 }
 
-pub fn classify_revive(revive: &ReviveEntry, min_chance: u64) -> ReviveClass {
-    if revive.result == "success" {
+pub fn classify_revive(revive: &ReviveEntry, min_chance: u64, scope: &ResolvedScope) -> ReviveClass {
+    if !scope.covers(revive) {
+        ReviveClass::OutOfScope
+    } else if revive.result == "success" {
         ReviveClass::Success
     } else if revive.result == "failure" && revive.chance >= min_chance as f32 {
         ReviveClass::FailedCounted
@@ -96,3 +232,114 @@ pub fn format_with_commas(number: u64) -> String {
     }
     chars.into_iter().collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINKED: &str =
+        r#"Hospitalized by <a href = "http://www.torn.com/profiles.php?XID=2531272">Duke</a>"#;
+    const MUGGED: &str =
+        r#"Mugged by <a href = "http://www.torn.com/profiles.php?XID=1441750">Chedburn</a>"#;
+    const ANONYMOUS: &str = "Hospitalized by someone";
+    const LOST_TO: &str =
+        r#"Lost to <a href = "http://www.torn.com/profiles.php?XID=2531272">Duke</a>"#;
+    const LEGACY: &str = "Hospitalized by Duke";
+    const OVERDOSE: &str = "Overdosed on Xanax";
+
+    fn revive(reason: &str) -> ReviveEntry {
+        serde_json::from_value(serde_json::json!({
+            "id": "1",
+            "timestamp": 0,
+            "result": "success",
+            "chance": 100.0,
+            "reviver_id": 1,
+            "reviver_faction": 1,
+            "target_id": 2,
+            "target_faction": 2,
+            "target_hospital_reason": reason,
+            "target_early_discharge": false,
+            "target_last_action": { "status": "Online", "timestamp": 0 },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn parses_hospital_reasons() {
+        assert_eq!(
+            HospitalCause::parse(LINKED),
+            HospitalCause::Attacked {
+                attacker_id: Some(2531272)
+            }
+        );
+        assert_eq!(
+            HospitalCause::parse(MUGGED),
+            HospitalCause::Attacked {
+                attacker_id: Some(1441750)
+            }
+        );
+        assert_eq!(
+            HospitalCause::parse(ANONYMOUS),
+            HospitalCause::Attacked { attacker_id: None }
+        );
+        assert_eq!(
+            HospitalCause::parse(LEGACY),
+            HospitalCause::Attacked { attacker_id: None }
+        );
+        assert_eq!(HospitalCause::parse(LOST_TO), HospitalCause::LostAttack);
+        assert_eq!(HospitalCause::parse(OVERDOSE), HospitalCause::Other);
+    }
+
+    #[test]
+    fn defensive_scope_covers_known_attackers_of_listed_factions() {
+        let scope = ResolvedScope::new(
+            ContractScope::Defensive {
+                attacker_factions: vec![9],
+            },
+            HashMap::from([(2531272, 9), (1441750, 10)]),
+        );
+
+        assert!(scope.covers(&revive(LINKED)));
+        assert!(!scope.covers(&revive(MUGGED)));
+        assert!(!scope.covers(&revive(ANONYMOUS)));
+        assert!(!scope.covers(&revive(LEGACY)));
+        assert!(!scope.covers(&revive(LOST_TO)));
+        assert!(!scope.covers(&revive(OVERDOSE)));
+    }
+
+    #[test]
+    fn unresolved_attacker_is_outside_a_defensive_scope() {
+        let scope = ResolvedScope::new(
+            ContractScope::Defensive {
+                attacker_factions: vec![9],
+            },
+            HashMap::new(),
+        );
+
+        assert!(!scope.covers(&revive(LINKED)));
+    }
+
+    #[test]
+    fn offensive_and_all_scopes() {
+        let offensive = ResolvedScope::new(ContractScope::Offensive, HashMap::new());
+        assert!(offensive.covers(&revive(LOST_TO)));
+        assert!(!offensive.covers(&revive(LINKED)));
+        assert!(!offensive.covers(&revive(OVERDOSE)));
+
+        let all = ResolvedScope::new(ContractScope::All, HashMap::new());
+        for reason in [LINKED, ANONYMOUS, LEGACY, LOST_TO, OVERDOSE] {
+            assert!(all.covers(&revive(reason)));
+        }
+    }
+
+    #[test]
+    fn only_defensive_scopes_resolve_linked_attackers() {
+        let revives = [revive(LINKED), revive(ANONYMOUS), revive(LINKED), revive(MUGGED)];
+        let defensive = ContractScope::Defensive {
+            attacker_factions: vec![9],
+        };
+
+        assert_eq!(defensive.attackers_to_resolve(&revives), vec![1441750, 2531272]);
+        assert!(ContractScope::Offensive.attackers_to_resolve(&revives).is_empty());
+    }
+}
@@ -0,0 +1,118 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{watch, Notify};
+
+/// Coordinates stopping the bot on SIGTERM/SIGINT.
+///
+/// Once triggered, no new work may start: background loops stop at their next wait and
+/// commands are turned away. Work that already began holds a [`WorkGuard`], and
+/// [`Shutdown::wait_idle`] waits for those to be dropped before the bot disconnects.
+#[derive(Clone)]
+pub struct Shutdown {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    triggered: watch::Sender<bool>,
+    in_flight: AtomicUsize,
+    idle: Notify,
+}
+
+/// Keeps shutdown waiting while a unit of work (a sync, a command, a backfill page) runs.
+pub struct WorkGuard {
+    inner: Arc<Inner>,
+}
+
+impl Drop for WorkGuard {
+    fn drop(&mut self) {
+        if self.inner.in_flight.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.inner.idle.notify_waiters();
+        }
+    }
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                triggered: watch::channel(false).0,
+                in_flight: AtomicUsize::new(0),
+                idle: Notify::new(),
+            }),
+        }
+    }
+
+    pub fn trigger(&self) {
+        self.inner.triggered.send_replace(true);
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.inner.triggered.borrow()
+    }
+
+    /// Resolves once shutdown has been triggered.
+    pub async fn triggered(&self) {
+        let mut rx = self.inner.triggered.subscribe();
+        // The sender lives in `self`, so this can't fail while we wait.
+        let _ = rx.wait_for(|triggered| *triggered).await;
+    }
+
+    /// Sleeps for `duration`, returning `false` early if shutdown is triggered meanwhile.
+    pub async fn sleep(&self, duration: Duration) -> bool {
+        tokio::select! {
+            _ = tokio::time::sleep(duration) => !self.is_triggered(),
+            _ = self.triggered() => false,
+        }
+    }
+
+    /// Registers a unit of work, or returns `None` when shutdown has already begun.
+    pub fn begin(&self) -> Option<WorkGuard> {
+        self.inner.in_flight.fetch_add(1, Ordering::AcqRel);
+        let guard = WorkGuard {
+            inner: self.inner.clone(),
+        };
+        if self.is_triggered() {
+            return None;
+        }
+        Some(guard)
+    }
+
+    /// Waits until no work is in flight, giving up after `timeout`.
+    pub async fn wait_idle(&self, timeout: Duration) -> bool {
+        let wait = async {
+            loop {
+                let idle = self.inner.idle.notified();
+                if self.inner.in_flight.load(Ordering::Acquire) == 0 {
+                    return;
+                }
+                idle.await;
+            }
+        };
+        tokio::time::timeout(timeout, wait).await.is_ok()
+    }
+}
+
+/// Resolves on SIGINT (Ctrl+C) or, on Unix, SIGTERM.
+pub async fn wait_for_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = sigterm.recv() => {}
+                }
+                return;
+            }
+            Err(e) => log::error!("Failed to listen for SIGTERM: {e}"),
+        }
+    }
+
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        log::error!("Failed to listen for Ctrl+C: {e}");
+        std::future::pending::<()>().await;
+    }
+}
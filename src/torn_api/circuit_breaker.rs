@@ -0,0 +1,85 @@
+use std::time::{Duration, Instant};
+
+use crate::torn_api::error::Unavailable;
+
+/// Consecutive failures after which requests stop being sent to Torn.
+const FAILURE_THRESHOLD: u32 = 5;
+
+/// How long the breaker stays open after tripping on consecutive failures.
+const COOLDOWN: Duration = Duration::from_secs(60);
+
+/// How long the half-open probe may take to report back before another request is
+/// let through in its place (it may have ended without reaching Torn at all).
+const PROBE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Stops hammering Torn once it looks down, so callers fail fast instead of
+/// each sitting through their whole retry budget.
+///
+/// After the cooldown the breaker is half-open: exactly one request is let through as
+/// a probe while every other request keeps failing fast. A success closes the breaker;
+/// a failure re-opens it straight away since the failure streak was never reset.
+#[derive(Default)]
+pub(crate) struct CircuitBreaker {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    /// When the half-open probe was let through, while it has not reported back.
+    probe_since: Option<Instant>,
+    last_error: String,
+}
+
+impl CircuitBreaker {
+    /// Errors while the breaker is open, and while half-open for everything but the
+    /// one probe, which this lets through. Returns whether the caller is that probe.
+    pub fn check(&mut self, now: Instant) -> Result<bool, Unavailable> {
+        let Some(until) = self.open_until else {
+            return Ok(false);
+        };
+        if until > now {
+            return Err(Unavailable {
+                reason: self.last_error.clone(),
+                retry_after: Some(until - now),
+            });
+        }
+
+        match self.probe_since {
+            Some(since) if now.duration_since(since) < PROBE_TIMEOUT => Err(Unavailable {
+                reason: self.last_error.clone(),
+                retry_after: Some(PROBE_TIMEOUT - now.duration_since(since)),
+            }),
+            _ => {
+                self.probe_since = Some(now);
+                Ok(true)
+            }
+        }
+    }
+
+    /// Gives the probe slot back when the probe ended without reaching Torn (no key
+    /// free, no key with access), so the next request can probe right away.
+    pub fn release_probe(&mut self) {
+        self.probe_since = None;
+    }
+
+    pub fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+        self.open_until = None;
+        self.probe_since = None;
+    }
+
+    pub fn record_failure(&mut self, now: Instant, reason: String) {
+        self.consecutive_failures += 1;
+        self.last_error = reason;
+        if self.consecutive_failures >= FAILURE_THRESHOLD {
+            self.open_until = Some(now + COOLDOWN);
+            self.probe_since = None;
+        }
+    }
+
+    /// Opens the breaker right away, for errors that say Torn is off for a while
+    /// (API disabled, IP blocked).
+    pub fn trip(&mut self, now: Instant, duration: Duration, reason: String) {
+        self.consecutive_failures = self.consecutive_failures.max(FAILURE_THRESHOLD);
+        self.last_error = reason;
+        self.open_until = Some(now + duration);
+        self.probe_since = None;
+    }
+}
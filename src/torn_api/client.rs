@@ -1,12 +1,27 @@
+use std::collections::HashMap;
 use std::sync::Mutex;
+use std::time::Instant;
 
 use log::warn;
+use rand::Rng;
+use mongodb::bson::doc;
+use mongodb::bson::oid::ObjectId;
+use tokio::sync::broadcast;
 use tokio::time::{sleep, Duration};
 use torn_api::executor::{Executor, ExecutorExt};
-use torn_api::request::{ApiResponse, IntoRequest};
-use torn_api::{ApiError, Error as TornError};
-use torn_api::models::{FactionBasicResponse, FactionId, RevivesFullResponse, UserBasicResponse, UserDiscordPathId, UserProfileResponse};
-use torn_api::parameters::ApiSortDesc;
+use torn_api::request::{ApiRequest, ApiResponse, IntoRequest};
+use torn_api::ApiError;
+use torn_api::models::{FactionBasicResponse, FactionId, RevivesFullResponse, UserBasicResponse, UserDiscordPathId, UserFactionResponse, UserProfileResponse};
+use torn_api::parameters::{ApiLimit1000, ApiSortDesc};
+
+use crate::database::structures::APIKey as DbAPIKey;
+use crate::database::Database;
+use crate::torn_api::circuit_breaker::CircuitBreaker;
+use crate::torn_api::error::{TornError, Unavailable};
+use crate::torn_api::key_access::{KeyAccess, Route};
+use crate::torn_api::rate_limit::{RateWindow, OWNER_RATE_LIMIT, WINDOW};
+use crate::torn_api::replay::Replay;
+use crate::torn_api::Priority;
 
 /// A single Torn API key in the rotation pool.
 #[derive(Clone)]
@@ -14,52 +29,229 @@ pub struct APIKey {
     pub key: String,
     pub rate_limit: u32,
     pub owner: String,
+    /// Torn player id of the key owner. Keys of the same player share one rate limit;
+    /// when unknown, keys are grouped by `owner` name instead.
+    pub owner_id: Option<u64>,
+    /// `_id` of the backing `api_keys` document, if the key came from the database.
+    pub db_id: Option<ObjectId>,
+    /// Selections (and faction) the key may be used for.
+    pub access: KeyAccess,
+}
+
+/// Identity used to group keys that share the per-player rate limit.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum OwnerGroup {
+    Player(u64),
+    Name(String),
+}
+
+impl APIKey {
+    fn owner_group(&self) -> OwnerGroup {
+        match self.owner_id {
+            Some(id) => OwnerGroup::Player(id),
+            None => OwnerGroup::Name(self.owner.clone()),
+        }
+    }
+}
+
+/// How much a key in the rotation is used.
+#[derive(Debug, Clone, Copy)]
+pub struct KeyUsage {
+    pub last_minute: usize,
+    /// Requests not yet added to the key's stored `requests` count.
+    pub unsaved: u64,
+    pub rate_limit: u32,
+}
+
+/// A key that was dropped from the rotation because Torn rejected it.
+#[derive(Debug, Clone)]
+pub struct RemovedKey {
+    pub owner: String,
+    pub db_id: Option<ObjectId>,
+    pub code: u16,
+    pub message: String,
 }
 
 struct KeyEntry {
+    key: String,
     /// Pre-configured reqwest client with the `Authorization: ApiKey {key}` header.
     client: reqwest::Client,
     owner: String,
-    rate_limit: u32,
-    remaining: u32,
+    group: OwnerGroup,
+    db_id: Option<ObjectId>,
+    /// Requests made with this key in the last minute, capped by the key's own rate limit.
+    window: RateWindow,
+    access: KeyAccess,
+    /// Requests made with this key that are not yet added to its stored `requests`
+    /// count (see [`TornAPI::flush_usage`]).
+    unsaved: u64,
+    /// Routes Torn refused for this key (error 16) since its access was last inspected.
+    denied: Vec<Route>,
+}
+
+impl KeyEntry {
+    fn new(key: APIKey) -> Self {
+        let client = build_reqwest_client(&key.key);
+        let group = key.owner_group();
+        KeyEntry {
+            client,
+            group,
+            window: RateWindow::new(key.rate_limit),
+            key: key.key,
+            owner: key.owner,
+            db_id: key.db_id,
+            access: key.access,
+            unsaved: 0,
+            denied: Vec::new(),
+        }
+    }
+
+    fn serves(&self, route: &Route, faction: Option<u64>) -> bool {
+        self.access.allows(route, faction) && !self.denied.contains(route)
+    }
 }
 
 struct RotationState {
     keys: Vec<KeyEntry>,
-    last_reset: i64,
+    /// Requests per key owner in the last minute, capped by Torn's per-player limit.
+    owners: HashMap<OwnerGroup, RateWindow>,
     key_used: usize,
+    /// Requests currently sleeping for a key.
+    waiting: Vec<Waiter>,
+}
+
+/// A request sleeping for a key, with what it needs a key for.
+#[derive(Clone, PartialEq)]
+struct Waiter {
+    priority: Priority,
+    route: Route,
+    faction: Option<u64>,
+}
+
+impl RotationState {
+    /// Uses up the budget of the owner of `key`, after Torn said they hit their limit.
+    fn saturate_owner(&mut self, key: &str, now: Instant) {
+        let Some(group) = self.keys.iter().find(|k| k.key == key).map(|k| k.group.clone()) else {
+            return;
+        };
+        self.owners
+            .entry(group)
+            .or_insert_with(|| RateWindow::new(OWNER_RATE_LIMIT))
+            .fill(now);
+    }
+
+    /// Whether a higher lane is queued for a key this request could also take. Waiters
+    /// that need other keys (e.g. public lookups while this is a faction-only sync) don't
+    /// hold it back.
+    fn higher_lane_waiting(&self, priority: Priority, route: &Route, faction: Option<u64>) -> bool {
+        self.waiting
+            .iter()
+            .filter(|waiter| waiter.priority > priority)
+            .any(|waiter| {
+                self.keys.iter().any(|k| {
+                    k.serves(route, faction) && k.serves(&waiter.route, waiter.faction)
+                })
+            })
+    }
+}
+
+/// Marks a request as waiting for a key for as long as it lives, so the list stays
+/// right even when the waiting future is dropped.
+struct WaitingGuard<'a> {
+    state: &'a Mutex<RotationState>,
+    waiter: Waiter,
+}
+
+impl<'a> WaitingGuard<'a> {
+    fn new(state: &'a Mutex<RotationState>, waiter: Waiter) -> Self {
+        state
+            .lock()
+            .expect("rotation state mutex poisoned")
+            .waiting
+            .push(waiter.clone());
+        Self { state, waiter }
+    }
+}
+
+impl Drop for WaitingGuard<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.state.lock() {
+            if let Some(i) = state.waiting.iter().position(|w| *w == self.waiter) {
+                state.waiting.swap_remove(i);
+            }
+        }
+    }
 }
 
 /// Multi-key, rate-limited Torn API client.
 ///
-/// Implements [`torn_api::executor::Executor`] for `&TornAPI` by selecting a key
-/// from the rotation per request, replicating the previous hand-rolled wrapper's
-/// behaviour: per-minute rate counting, key removal on invalid-key errors, and
-/// backoff for transient errors.
+/// Implements [`torn_api::executor::Executor`] for [`Lane`] (and `&TornAPI`, which
+/// uses the interactive lane) by selecting a key from the rotation per request.
+/// Only keys whose [`KeyAccess`] covers the requested selection (and faction, for
+/// faction-scoped lanes) are picked, so faction keys and public donated keys share
+/// one pool.
+/// Every key is limited by a sliding one-minute window of its own, and keys of the
+/// same player additionally share Torn's per-player budget. Keys are removed on
+/// invalid-key errors.
+///
+/// Every request has a deadline and attempt budget set by its lane; network and
+/// transient Torn errors are retried with jittered backoff within that budget, and
+/// a circuit breaker fails requests fast with [`TornError::Unavailable`] while Torn
+/// is down.
+///
+/// In replay mode (see [`TornAPI::with_replay`]) responses come from recorded
+/// fixtures instead of the network; key rotation and error handling stay the same.
 #[derive(Clone)]
 pub struct TornAPI {
     state: std::sync::Arc<Mutex<RotationState>>,
+    breaker: std::sync::Arc<Mutex<CircuitBreaker>>,
+    removals: broadcast::Sender<RemovedKey>,
+    replay: Option<std::sync::Arc<Replay>>,
 }
 
 enum ErrorAction {
     RemoveKey,
-    Retry(u64),
+    /// The key lacks access to this selection — stop routing it there.
+    AccessDenied,
+    /// The key owner hit Torn's per-player limit — move on to another owner's key.
+    OwnerLimited,
+    /// Transient error — retry after roughly this long.
+    Retry(Duration),
+    /// Torn refuses all requests for a while — open the circuit breaker.
+    Outage(Duration),
     Fatal,
 }
 
+/// Whether Torn rejected the key itself (rather than the request).
+pub(super) fn is_key_error(code: u16) -> bool {
+    matches!(classify_error_code(code), ErrorAction::RemoveKey)
+}
+
 fn classify_error_code(code: u16) -> ErrorAction {
     match code {
         // Key-specific errors — remove and retry with another key.
-        1 | 2 | 10 | 13 | 16 | 18 => ErrorAction::RemoveKey,
+        1 | 2 | 10 | 13 | 18 => ErrorAction::RemoveKey,
+        16 => ErrorAction::AccessDenied,
+        5 => ErrorAction::OwnerLimited,
+        // IP block / API disabled.
+        8 | 9 => ErrorAction::Outage(Duration::from_secs(30)),
         // Transient errors — wait and retry.
-        5 => ErrorAction::Retry(60),
-        8 | 9 => ErrorAction::Retry(30),
-        15 | 17 => ErrorAction::Retry(5),
+        15 | 17 => ErrorAction::Retry(Duration::from_secs(5)),
         // Request-level or unknown errors — do not retry or remove keys.
         _ => ErrorAction::Fatal,
     }
 }
 
+/// Base delay before retrying after a network error, doubled on every attempt.
+const NETWORK_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Spreads retries of concurrent requests out to 50–150% of `base`.
+fn jittered(base: Duration) -> Duration {
+    base.min(MAX_BACKOFF)
+        .mul_f64(rand::rng().random_range(0.5..1.5))
+}
+
 fn build_reqwest_client(api_key: &str) -> reqwest::Client {
     let mut headers = reqwest::header::HeaderMap::with_capacity(1);
 
@@ -85,120 +277,414 @@ fn build_reqwest_client(api_key: &str) -> reqwest::Client {
 /// Result of trying to reserve a key for one request.
 enum Pick {
     /// A key was reserved for use.
-    Ready { client: reqwest::Client, owner: String },
-    /// All keys are exhausted until the rate-limit window resets; sleep this many seconds.
-    Exhausted { wait_secs: u64 },
+    Ready {
+        key: String,
+        client: reqwest::Client,
+        owner: String,
+    },
+    /// Every key (or its owner) used up the lane's budget, or a higher lane is queued
+    /// ahead; sleep this long before trying again.
+    Exhausted { wait: Duration },
     /// No keys remain in the pool at all.
     NoKeys,
+    /// Keys remain, but none of them may be used for this request.
+    NoAccess,
 }
 
-fn pick_key(state: &mut RotationState) -> Pick {
-    let now = chrono::Utc::now().timestamp();
-    if now - state.last_reset >= 60 {
-        state.last_reset = now;
-        state.key_used = 0;
-        for k in state.keys.iter_mut() {
-            k.remaining = k.rate_limit;
-        }
-    }
+/// How long a lower lane backs off while a higher lane is queued for a key.
+const YIELD_WAIT: Duration = Duration::from_millis(250);
 
+fn pick_key(
+    state: &mut RotationState,
+    priority: Priority,
+    route: &Route,
+    faction: Option<u64>,
+) -> Pick {
     if state.keys.is_empty() {
         return Pick::NoKeys;
     }
+    if !state.keys.iter().any(|k| k.serves(route, faction)) {
+        return Pick::NoAccess;
+    }
+
+    // Let queued higher-priority requests take the next free slot first.
+    if state.higher_lane_waiting(priority, route, faction) {
+        return Pick::Exhausted { wait: YIELD_WAIT };
+    }
 
+    let now = Instant::now();
     let n = state.keys.len();
     let start = state.key_used % n;
-    let mut idx = start;
-    loop {
-        if state.keys[idx].remaining > 0 {
-            state.keys[idx].remaining -= 1;
+    let mut wait = WINDOW;
+
+    for offset in 0..n {
+        let idx = (start + offset) % n;
+        let entry = &mut state.keys[idx];
+        if !entry.serves(route, faction) {
+            continue;
+        }
+        let owner = state
+            .owners
+            .entry(entry.group.clone())
+            .or_insert_with(|| RateWindow::new(OWNER_RATE_LIMIT));
+
+        let key_wait = entry.window.wait(now, priority);
+        let owner_wait = owner.wait(now, priority);
+
+        if key_wait.is_zero() && owner_wait.is_zero() {
+            entry.window.record(now);
+            entry.unsaved += 1;
+            owner.record(now);
             state.key_used = idx + 1;
-            let entry = &state.keys[idx];
             return Pick::Ready {
+                key: entry.key.clone(),
                 client: entry.client.clone(),
                 owner: entry.owner.clone(),
             };
         }
-        idx = (idx + 1) % n;
-        if idx == start {
-            break;
-        }
+
+        wait = wait.min(key_wait.max(owner_wait));
     }
 
-    let wait = (60 - (now - state.last_reset)).max(1) as u64;
-    Pick::Exhausted { wait_secs: wait }
+    Pick::Exhausted { wait }
 }
 
 impl TornAPI {
     /// Build a new client rotating over the provided keys.
     pub fn new(keys: Vec<APIKey>) -> TornAPI {
-        let entries = keys
-            .into_iter()
-            .map(|k| {
-                let client = build_reqwest_client(&k.key);
-                KeyEntry {
-                    client,
-                    owner: k.owner,
-                    rate_limit: k.rate_limit,
-                    remaining: k.rate_limit,
-                }
-            })
-            .collect();
+        let entries = keys.into_iter().map(KeyEntry::new).collect();
 
         TornAPI {
             state: std::sync::Arc::new(Mutex::new(RotationState {
                 keys: entries,
-                last_reset: chrono::Utc::now().timestamp(),
+                owners: HashMap::new(),
                 key_used: 0,
+                waiting: Vec::new(),
             })),
+            breaker: std::sync::Arc::new(Mutex::new(CircuitBreaker::default())),
+            removals: broadcast::channel(16).0,
+            replay: None,
         }
     }
 
-    /// Add a new API key to the rotation at runtime.
-    pub async fn add_key(&self, key: APIKey) {
-        let client = build_reqwest_client(&key.key);
-        let entry = KeyEntry {
-            client,
-            owner: key.owner,
-            rate_limit: key.rate_limit,
-            remaining: key.rate_limit,
+    /// Serve every request from the given fixtures instead of calling Torn.
+    pub fn with_replay(mut self, replay: std::sync::Arc<Replay>) -> TornAPI {
+        self.replay = Some(replay);
+        self
+    }
+
+    /// Requests issued through the returned lane are scheduled with the given priority.
+    pub fn lane(&self, priority: Priority) -> Lane<'_> {
+        Lane {
+            api: self,
+            priority,
+            faction: None,
+        }
+    }
+
+    /// Executor that sends requests with exactly `key`, once, outside the rotation.
+    /// Used to check keys that are not (or not yet) part of the pool. When the key is
+    /// in the pool anyway, each request still counts against its (and its owner's) rate
+    /// window, waiting for a free slot like an interactive request would.
+    pub fn probe(&self, key: &str) -> KeyProbe<'_> {
+        KeyProbe {
+            api: self,
+            key: key.to_string(),
+            client: build_reqwest_client(key),
+        }
+    }
+
+    /// Takes a slot in the rate windows of `key` if it is in the rotation; otherwise
+    /// there is nothing to count against. Returns how long to wait when they are full.
+    fn reserve_probe(&self, key: &str, now: Instant) -> Result<(), Duration> {
+        let mut state = self.state.lock().expect("rotation state mutex poisoned");
+        let state = &mut *state;
+        let Some(entry) = state.keys.iter_mut().find(|k| k.key == key) else {
+            return Ok(());
         };
+        let owner = state
+            .owners
+            .entry(entry.group.clone())
+            .or_insert_with(|| RateWindow::new(OWNER_RATE_LIMIT));
+
+        let wait = entry
+            .window
+            .wait(now, Priority::Interactive)
+            .max(owner.wait(now, Priority::Interactive));
+        if !wait.is_zero() {
+            return Err(wait);
+        }
+        entry.window.record(now);
+        entry.unsaved += 1;
+        owner.record(now);
+        Ok(())
+    }
+
+    /// Ask Torn what `key` may access.
+    pub async fn inspect_key(&self, key: &str) -> Result<KeyAccess, TornError> {
+        let response = self.probe(key).key().info(|b| b).await?;
+        Ok(KeyAccess::from_info(&response.info))
+    }
+
+    /// Replace the known access of a key in the rotation.
+    pub fn set_access(&self, key: &str, access: KeyAccess) {
+        let mut state = self.state.lock().expect("rotation state mutex poisoned");
+        if let Some(entry) = state.keys.iter_mut().find(|k| k.key == key) {
+            entry.access = access;
+            entry.denied.clear();
+        }
+    }
+
+    /// Inspects every key whose access is not known yet, updating the rotation and
+    /// the backing `api_keys` documents. Keys that can't be inspected stay as they are.
+    pub async fn learn_access(&self) {
+        let unknown: Vec<(String, String, Option<ObjectId>)> = {
+            let state = self.state.lock().expect("rotation state mutex poisoned");
+            state
+                .keys
+                .iter()
+                .filter(|k| !k.access.is_known())
+                .map(|k| (k.key.clone(), k.owner.clone(), k.db_id))
+                .collect()
+        };
+
+        for (key, owner, db_id) in unknown {
+            let access = match self.inspect_key(&key).await {
+                Ok(access) => access,
+                Err(e) => {
+                    warn!("Failed to inspect Torn API key of {owner}: {e:#}");
+                    continue;
+                }
+            };
+
+            // Torn's answer wins over the faction the key was configured for.
+            let configured = {
+                let state = self.state.lock().expect("rotation state mutex poisoned");
+                state.keys.iter().find(|k| k.key == key).and_then(|k| k.access.faction_id)
+            };
+            if configured.is_some() && configured != access.faction_id {
+                warn!(
+                    "Torn API key of {owner} was configured for faction {configured:?}, but has faction access to {:?}",
+                    access.faction_id
+                );
+            }
+
+            if let Some(id) = db_id {
+                let result = Database::update_doc::<DbAPIKey>(
+                    doc! { "_id": id },
+                    doc! { "$set": { "access": mongodb::bson::to_bson(&access).unwrap_or_default() } },
+                )
+                .await;
+                if let Err(e) = result {
+                    warn!("Failed to store access of Torn API key of {owner}: {e:#}");
+                }
+            }
+
+            log::info!(
+                "Torn API key of {owner}: access level {}, faction {:?}",
+                access.level,
+                access.faction_id
+            );
+            self.set_access(&key, access);
+        }
+    }
+
+    /// Subscribe to keys dropped from the rotation after Torn rejected them.
+    pub fn subscribe_removals(&self) -> broadcast::Receiver<RemovedKey> {
+        self.removals.subscribe()
+    }
+
+    /// Add a new API key to the rotation at runtime. A key that is already in the
+    /// rotation keeps its recent usage but takes the new owner, limit and access.
+    pub async fn add_key(&self, key: APIKey) {
+        let mut state = self.state.lock().expect("rotation state mutex poisoned");
+        match state.keys.iter_mut().find(|k| k.key == key.key) {
+            Some(entry) => {
+                entry.group = key.owner_group();
+                entry.window.set_limit(key.rate_limit);
+                entry.owner = key.owner;
+                entry.db_id = key.db_id;
+                entry.access = key.access;
+                entry.denied.clear();
+            }
+            None => state.keys.push(KeyEntry::new(key)),
+        }
+    }
+
+    /// Drop the key backed by the given `api_keys` document from the rotation.
+    /// Returns false if it wasn't in the rotation.
+    pub fn remove_key(&self, db_id: ObjectId) -> bool {
+        let mut state = self.state.lock().expect("rotation state mutex poisoned");
+        let before = state.keys.len();
+        state.keys.retain(|k| k.db_id != Some(db_id));
+        state.key_used = 0;
+        state.keys.len() != before
+    }
+
+    /// Change the per-minute limit of the key backed by the given `api_keys` document.
+    /// Returns false if it isn't in the rotation.
+    pub fn set_rate_limit(&self, db_id: ObjectId, rate_limit: u32) -> bool {
+        let mut state = self.state.lock().expect("rotation state mutex poisoned");
+        match state.keys.iter_mut().find(|k| k.db_id == Some(db_id)) {
+            Some(entry) => {
+                entry.window.set_limit(rate_limit);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Usage of the key backed by the given `api_keys` document, `None` if it isn't
+    /// in the rotation.
+    pub fn key_usage(&self, db_id: ObjectId) -> Option<KeyUsage> {
         let mut state = self.state.lock().expect("rotation state mutex poisoned");
-        state.keys.push(entry);
+        let entry = state.keys.iter_mut().find(|k| k.db_id == Some(db_id))?;
+        Some(KeyUsage {
+            last_minute: entry.window.used(Instant::now()),
+            unsaved: entry.unsaved,
+            rate_limit: entry.window.limit(),
+        })
+    }
+
+    /// Adds the requests made since the last flush to each stored key's `requests`.
+    /// Counts that fail to store are kept for the next flush.
+    pub async fn flush_usage(&self) {
+        let pending: Vec<(ObjectId, u64)> = {
+            let mut state = self.state.lock().expect("rotation state mutex poisoned");
+            state
+                .keys
+                .iter_mut()
+                .filter_map(|k| {
+                    let id = k.db_id?;
+                    let unsaved = std::mem::take(&mut k.unsaved);
+                    (unsaved > 0).then_some((id, unsaved))
+                })
+                .collect()
+        };
+
+        for (id, unsaved) in pending {
+            let result = Database::update_doc::<DbAPIKey>(
+                doc! { "_id": id },
+                doc! { "$inc": { "requests": unsaved as i64 } },
+            )
+            .await;
+            if let Err(e) = result {
+                log::warn!("Failed to store the request count of key {id}: {e:#}");
+                let mut state = self.state.lock().expect("rotation state mutex poisoned");
+                if let Some(entry) = state.keys.iter_mut().find(|k| k.db_id == Some(id)) {
+                    entry.unsaved += unsaved;
+                }
+            }
+        }
     }
 
     /// Fetch the full profile for a Torn user (by Torn user id *or* Discord id).
     pub async fn get_player_profile(
         &self,
         id: UserDiscordPathId,
+        priority: Priority,
     ) -> Result<UserProfileResponse, TornError> {
-        self.user().profile_for_id(id, |b| b).await
+        self.lane(priority).user().profile_for_id(id, |b| b).await
     }
 
     /// Fetch the lightweight basic info for a Torn user (name + minimal fields).
     pub async fn get_player_basic(
         &self,
         id: UserDiscordPathId,
+        priority: Priority,
     ) -> Result<UserBasicResponse, TornError> {
-        self.user().basic_for_id(id, |b| b).await
+        self.lane(priority).user().basic_for_id(id, |b| b).await
+    }
+
+    /// Fetch the faction a Torn user is currently in, if any.
+    pub async fn get_player_faction(
+        &self,
+        id: UserDiscordPathId,
+        priority: Priority,
+    ) -> Result<UserFactionResponse, TornError> {
+        self.lane(priority).user().faction_for_id(id, |b| b).await
     }
 
     /// Fetch a faction's basic details (id, name, …).
     pub async fn get_faction_basic(
         &self,
         id: FactionId,
+        priority: Priority,
     ) -> Result<FactionBasicResponse, TornError> {
-        self.faction().basic_for_id(id, |b| b).await
+        self.lane(priority).faction().basic_for_id(id, |b| b).await
     }
 
-    /// Fetch the key owner faction's simplified revives since `from`.
-    pub async fn get_revives_full(&self, from: u64) -> Result<RevivesFullResponse, TornError> {
-        self.faction()
-            .revives_full(|b| b.api_from(from as i32).api_sort_desc(ApiSortDesc::Asc))
+    /// Fetch up to `limit` (at most 1000) of a faction's simplified revives since `from` (up to
+    /// `to`, if given), oldest first, using one of its own keys. Hospital reasons keep their
+    /// profile links so contract scopes can tell who the attacker was.
+    pub async fn get_revives_full(
+        &self,
+        faction_id: u64,
+        from: u64,
+        to: Option<u64>,
+        limit: u32,
+    ) -> Result<RevivesFullResponse, TornError> {
+        let limit = ApiLimit1000::new(limit.clamp(1, 1000) as i32)
+            .expect("limit clamped to the allowed range");
+        self.lane(Priority::Background)
+            .for_faction(faction_id)
+            .faction()
+            .revives_full(|b| {
+                b.api_from(from as i32)
+                    .maybe_api_to(to.map(|to| to as i32))
+                    .api_limit1000(limit)
+                    .api_sort_desc(ApiSortDesc::Asc)
+                    .api_strip_tags_true(false)
+            })
             .await
     }
 }
 
+impl TornAPI {
+    /// Persists a removal to `api_keys` so the key is not loaded again on restart,
+    /// then announces it to subscribers (who notify the donor).
+    pub(super) async fn record_removal(&self, removed: RemovedKey) {
+        if let Some(id) = removed.db_id {
+            let result = Database::update_doc::<DbAPIKey>(
+                doc! { "_id": id },
+                doc! { "$set": {
+                    "valid": false,
+                    "error_code": removed.code as i32,
+                    "error_message": &removed.message,
+                    "invalidated_at": chrono::Utc::now().timestamp(),
+                } },
+            )
+            .await;
+
+            if let Err(e) = result {
+                warn!("Failed to mark API key of {} as invalid: {e:#}", removed.owner);
+            }
+        }
+
+        // Nobody listening is fine (e.g. before the bot finished starting).
+        let _ = self.removals.send(removed);
+    }
+}
+
+/// A [`TornAPI`] handle whose requests are scheduled in one [`Priority`] lane.
+#[derive(Clone, Copy)]
+pub struct Lane<'a> {
+    api: &'a TornAPI,
+    priority: Priority,
+    /// Only keys with faction access to this faction are used, for selections
+    /// about "the key owner's faction".
+    faction: Option<u64>,
+}
+
+impl Lane<'_> {
+    pub fn for_faction(self, faction_id: u64) -> Self {
+        Self {
+            faction: Some(faction_id),
+            ..self
+        }
+    }
+}
+
 impl Executor for &TornAPI {
     type Error = TornError;
 
@@ -206,83 +692,306 @@ impl Executor for &TornAPI {
     where
         R: IntoRequest,
     {
+        Executor::execute(self.lane(Priority::Interactive), request).await
+    }
+}
+
+impl Executor for Lane<'_> {
+    type Error = TornError;
+
+    async fn execute<R>(self, request: R) -> (R::Discriminant, Result<ApiResponse, Self::Error>)
+    where
+        R: IntoRequest,
+    {
+        let Lane {
+            api: this,
+            priority,
+            faction,
+        } = self;
         let (discriminant, api_request) = request.into_request();
-        let url = api_request.url();
+        let route = Route::from_path(&api_request.path);
+
+        let deadline = Instant::now() + priority.deadline();
+        let mut failures = 0u32;
+        // Whether this request holds the half-open probe slot. A probe that waits for a
+        // key keeps it instead of being refused by its own slot.
+        let mut probing = false;
+        let release_probe = |probing: bool| {
+            if probing {
+                this.breaker
+                    .lock()
+                    .expect("circuit breaker mutex poisoned")
+                    .release_probe();
+            }
+        };
 
         loop {
+            if !probing {
+                let check = this
+                    .breaker
+                    .lock()
+                    .expect("circuit breaker mutex poisoned")
+                    .check(Instant::now());
+                match check {
+                    Ok(probe) => probing = probe,
+                    Err(unavailable) => return (discriminant, Err(unavailable.into())),
+                }
+            }
+
             // --- reserve a key (brief critical section, no await held) ---
-            let (client, owner) = match {
-                let mut state = self.state.lock().expect("rotation state mutex poisoned");
-                pick_key(&mut state)
+            let (key, client, owner) = match {
+                let mut state = this.state.lock().expect("rotation state mutex poisoned");
+                pick_key(&mut state, priority, &route, faction)
             } {
-                Pick::Ready { client, owner } => (client, owner),
-                Pick::Exhausted { wait_secs } => {
-                    sleep(Duration::from_secs(wait_secs)).await;
+                Pick::Ready { key, client, owner } => (key, client, owner),
+                Pick::Exhausted { wait } => {
+                    if Instant::now() + wait > deadline {
+                        release_probe(probing);
+                        let unavailable = Unavailable {
+                            reason: "all API keys are at their rate limit".to_string(),
+                            retry_after: Some(wait),
+                        };
+                        return (discriminant, Err(unavailable.into()));
+                    }
+                    let _waiting = WaitingGuard::new(
+                        &this.state,
+                        Waiter {
+                            priority,
+                            route: route.clone(),
+                            faction,
+                        },
+                    );
+                    sleep(wait).await;
                     continue;
                 }
                 Pick::NoKeys => {
+                    release_probe(probing);
                     return (discriminant, Err(TornError::Api(ApiError::KeyIsEmpty)));
                 }
+                Pick::NoAccess => {
+                    release_probe(probing);
+                    warn!("No Torn API key has access to {route} (faction {faction:?})");
+                    return (
+                        discriminant,
+                        Err(TornError::Api(ApiError::InsufficientAccessLevel)),
+                    );
+                }
             };
 
-            // --- issue the HTTP request ---
-            let response = match client.get(&url).send().await {
-                Ok(r) => r,
-                Err(e) => return (discriminant, Err(TornError::Network(e))),
-            };
-            let status = response.status();
-            let bytes = match response.bytes().await {
-                Ok(b) => b,
-                Err(e) => return (discriminant, Err(TornError::Network(e))),
-            };
+            // --- issue the HTTP request; anything that falls through is retried ---
+            // Whatever Torn answers settles the probe, so the next attempt checks again.
+            probing = false;
+            let (reason, retry_in) = match this.send(&client, &api_request).await {
+                Err(e) => (
+                    format!("network error: {e}"),
+                    jittered(NETWORK_BACKOFF * 2u32.pow(failures.min(5))),
+                ),
+                Ok(response) if response.status.is_server_error() => (
+                    format!("HTTP {}", response.status),
+                    jittered(NETWORK_BACKOFF * 2u32.pow(failures.min(5))),
+                ),
+                Ok(response) => {
+                    // --- classify Torn API errors and retry/remove/fatal accordingly ---
+                    let Some((code, message)) = response.body.as_deref().and_then(parse_error)
+                    else {
+                        this.breaker
+                            .lock()
+                            .expect("circuit breaker mutex poisoned")
+                            .record_success();
+                        return (discriminant, Ok(response));
+                    };
 
-            // --- classify Torn API errors and retry/remove/fatal accordingly ---
-            if bytes.starts_with(br#"{"error":{"#) {
-                #[derive(serde::Deserialize)]
-                struct ErrorBody<'a> {
-                    code: u16,
-                    error: &'a str,
-                }
-                #[derive(serde::Deserialize)]
-                struct ErrorContainer<'a> {
-                    #[serde(borrow)]
-                    error: ErrorBody<'a>,
-                }
+                    let action = classify_error_code(code);
+                    if matches!(
+                        action,
+                        ErrorAction::RemoveKey | ErrorAction::AccessDenied | ErrorAction::OwnerLimited
+                    ) {
+                        // Torn answered about the key, so it is up; this also settles a
+                        // half-open probe before the request moves on to another key.
+                        this.breaker
+                            .lock()
+                            .expect("circuit breaker mutex poisoned")
+                            .record_success();
+                    }
 
-                if let Ok(container) = serde_json::from_slice::<ErrorContainer>(&bytes) {
-                    let code = container.error.code;
-                    let message = container.error.error;
-                    match classify_error_code(code) {
+                    match action {
                         ErrorAction::RemoveKey => {
-                            {
+                            let db_id = {
                                 let mut state =
-                                    self.state.lock().expect("rotation state mutex poisoned");
-                                state.keys.retain(|k| k.owner != owner);
+                                    this.state.lock().expect("rotation state mutex poisoned");
+                                let db_id = state
+                                    .keys
+                                    .iter()
+                                    .find(|k| k.key == key)
+                                    .and_then(|k| k.db_id);
+                                state.keys.retain(|k| k.key != key);
                                 state.key_used = 0;
-                            }
+                                db_id
+                            };
                             warn!(
                                 "Removed invalid Torn API key (owner {owner}, code {code}: {message})"
                             );
+                            this.record_removal(RemovedKey {
+                                owner,
+                                db_id,
+                                code,
+                                message,
+                            })
+                            .await;
                             continue;
                         }
-                        ErrorAction::Retry(secs) => {
-                            warn!(
-                                "Torn API error code {code} ({message}), retrying in {secs}s"
-                            );
-                            sleep(Duration::from_secs(secs)).await;
+                        ErrorAction::AccessDenied => {
+                            warn!("Torn API key of {owner} has no access to {route} (code {code}: {message})");
+                            let mut state =
+                                this.state.lock().expect("rotation state mutex poisoned");
+                            if let Some(entry) = state.keys.iter_mut().find(|k| k.key == key) {
+                                entry.denied.push(route.clone());
+                            }
+                            continue;
+                        }
+                        ErrorAction::OwnerLimited => {
+                            warn!("Torn API key owner {owner} hit the rate limit (code {code}: {message})");
+                            this.state
+                                .lock()
+                                .expect("rotation state mutex poisoned")
+                                .saturate_owner(&key, Instant::now());
                             continue;
                         }
+                        ErrorAction::Outage(duration) => {
+                            let reason = format!("error {code}: {message}");
+                            warn!("Torn API outage ({reason}), pausing requests for {}s", duration.as_secs());
+                            this.breaker
+                                .lock()
+                                .expect("circuit breaker mutex poisoned")
+                                .trip(Instant::now(), duration, reason.clone());
+                            let unavailable = Unavailable {
+                                reason,
+                                retry_after: Some(duration),
+                            };
+                            return (discriminant, Err(unavailable.into()));
+                        }
+                        ErrorAction::Retry(base) => (format!("error {code}: {message}"), jittered(base)),
                         ErrorAction::Fatal => {
+                            this.breaker
+                                .lock()
+                                .expect("circuit breaker mutex poisoned")
+                                .record_success();
                             return (
                                 discriminant,
-                                Err(TornError::Api(ApiError::new(code, message))),
+                                Err(TornError::Api(ApiError::new(code, &message))),
                             );
                         }
                     }
                 }
+            };
+
+            failures += 1;
+            this.breaker
+                .lock()
+                .expect("circuit breaker mutex poisoned")
+                .record_failure(Instant::now(), reason.clone());
+
+            if failures >= priority.max_attempts() || Instant::now() + retry_in > deadline {
+                warn!("Giving up on Torn API request after {failures} failed attempt(s): {reason}");
+                let unavailable = Unavailable {
+                    reason,
+                    retry_after: None,
+                };
+                return (discriminant, Err(unavailable.into()));
             }
 
-            return (discriminant, Ok(ApiResponse { status, body: Some(bytes) }));
+            warn!("Torn API request failed ({reason}), retrying in {}ms", retry_in.as_millis());
+            sleep(retry_in).await;
         }
     }
-}
\ No newline at end of file
+}
+
+/// Executor returned by [`TornAPI::probe`]: one attempt with a fixed key. The attempt
+/// counts against the key's and its owner's rate windows like any request, but skips
+/// retries and the circuit breaker.
+pub struct KeyProbe<'a> {
+    api: &'a TornAPI,
+    key: String,
+    client: reqwest::Client,
+}
+
+impl Executor for &KeyProbe<'_> {
+    type Error = TornError;
+
+    async fn execute<R>(self, request: R) -> (R::Discriminant, Result<ApiResponse, Self::Error>)
+    where
+        R: IntoRequest,
+    {
+        let (discriminant, api_request) = request.into_request();
+
+        let deadline = Instant::now() + Priority::Interactive.deadline();
+        while let Err(wait) = self.api.reserve_probe(&self.key, Instant::now()) {
+            if Instant::now() + wait > deadline {
+                let unavailable = Unavailable {
+                    reason: "the API key is at its rate limit".to_string(),
+                    retry_after: Some(wait),
+                };
+                return (discriminant, Err(unavailable.into()));
+            }
+            sleep(wait).await;
+        }
+
+        let result = match self.api.send(&self.client, &api_request).await {
+            Err(e) => Err(TornError::Network(e)),
+            Ok(response) if response.status.is_server_error() => Err(Unavailable {
+                reason: format!("HTTP {}", response.status),
+                retry_after: None,
+            }
+            .into()),
+            Ok(response) => match response.body.as_deref().and_then(parse_error) {
+                Some((code, message)) => Err(TornError::Api(ApiError::new(code, &message))),
+                None => Ok(response),
+            },
+        };
+        (discriminant, result)
+    }
+}
+
+impl TornAPI {
+    async fn send(
+        &self,
+        client: &reqwest::Client,
+        request: &ApiRequest,
+    ) -> Result<ApiResponse, reqwest::Error> {
+        match &self.replay {
+            Some(replay) => Ok(replay.respond(request)),
+            None => fetch(client, &request.url()).await,
+        }
+    }
+}
+
+async fn fetch(client: &reqwest::Client, url: &str) -> Result<ApiResponse, reqwest::Error> {
+    let response = client.get(url).send().await?;
+    let status = response.status();
+    let bytes = response.bytes().await?;
+    Ok(ApiResponse {
+        status,
+        body: Some(bytes),
+    })
+}
+
+/// Extracts the code and message of a Torn error body, `None` for regular responses.
+fn parse_error(bytes: &[u8]) -> Option<(u16, String)> {
+    if !bytes.starts_with(br#"{"error":{"#) {
+        return None;
+    }
+
+    #[derive(serde::Deserialize)]
+    struct ErrorBody<'a> {
+        code: u16,
+        error: &'a str,
+    }
+    #[derive(serde::Deserialize)]
+    struct ErrorContainer<'a> {
+        #[serde(borrow)]
+        error: ErrorBody<'a>,
+    }
+
+    let container = serde_json::from_slice::<ErrorContainer>(bytes).ok()?;
+    Some((container.error.code, container.error.error.to_string()))
+}
@@ -0,0 +1,75 @@
+use std::fmt;
+use std::time::Duration;
+
+use torn_api::ApiError;
+
+/// Error returned by requests made through [`TornAPI`](crate::torn_api::TornAPI).
+#[derive(Debug)]
+pub enum TornError {
+    /// Torn answered with an error code that is not worth retrying.
+    Api(ApiError),
+    Network(reqwest::Error),
+    Parsing(serde_json::Error),
+    /// Torn could not be reached within the request's deadline or attempt budget,
+    /// or the circuit breaker is open after repeated failures. The message is meant
+    /// to be shown to users as-is.
+    Unavailable(Unavailable),
+}
+
+/// Details of a request that gave up because Torn is down or overloaded.
+#[derive(Debug, Clone)]
+pub struct Unavailable {
+    pub reason: String,
+    /// How long until trying again is worthwhile, when known.
+    pub retry_after: Option<Duration>,
+}
+
+impl fmt::Display for Unavailable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Torn API is currently unavailable ({})", self.reason)?;
+        if let Some(after) = self.retry_after {
+            write!(f, ", try again in {}s", after.as_secs().max(1))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for TornError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Api(e) => write!(f, "Api error: {e}"),
+            Self::Network(e) => write!(f, "Network error: {e}"),
+            Self::Parsing(e) => write!(f, "Parsing error: {e}"),
+            Self::Unavailable(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for TornError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Api(e) => Some(e),
+            Self::Network(e) => Some(e),
+            Self::Parsing(e) => Some(e),
+            Self::Unavailable(_) => None,
+        }
+    }
+}
+
+impl From<ApiError> for TornError {
+    fn from(e: ApiError) -> Self {
+        Self::Api(e)
+    }
+}
+
+impl From<serde_json::Error> for TornError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Parsing(e)
+    }
+}
+
+impl From<Unavailable> for TornError {
+    fn from(e: Unavailable) -> Self {
+        Self::Unavailable(e)
+    }
+}
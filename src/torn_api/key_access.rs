@@ -0,0 +1,164 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use torn_api::models::key_info_response::Info;
+
+/// What a key is allowed to fetch, as reported by Torn's key info.
+///
+/// Stored with each donated key and used to route requests only to keys that can
+/// serve them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct KeyAccess {
+    /// Torn access level, 1 (public) to 4 (full). `0` means the key was not inspected
+    /// yet; see [`KeyAccess::allows`] for what such keys are used for.
+    pub level: u8,
+    /// Faction whose own data (e.g. `revivesFull`) the key can read. Only set when the
+    /// key owner granted faction API access.
+    #[serde(default)]
+    pub faction_id: Option<u64>,
+    /// Factions a key that was not inspected yet was configured to serve, e.g. every
+    /// faction of a revive source. Torn's answer replaces them once it is inspected.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub configured_factions: Vec<u64>,
+    /// Allowed selections per scope, lowercase (`"faction" -> ["basic", "revivesfull"]`).
+    #[serde(default)]
+    pub selections: BTreeMap<String, Vec<String>>,
+}
+
+impl KeyAccess {
+    /// Access of a key that has not been inspected, but is known to serve `faction_ids`.
+    pub fn factions(faction_ids: &[u64]) -> Self {
+        Self {
+            faction_id: faction_ids.first().copied(),
+            configured_factions: faction_ids.to_vec(),
+            ..Self::default()
+        }
+    }
+
+    pub fn from_info(info: &Info) -> Self {
+        fn names<T: ToString>(list: &[T]) -> Vec<String> {
+            list.iter().map(|s| s.to_string().to_lowercase()).collect()
+        }
+
+        let s = &info.selections;
+        let selections = BTreeMap::from([
+            ("company".to_string(), names(&s.company)),
+            ("faction".to_string(), names(&s.faction)),
+            ("market".to_string(), names(&s.market)),
+            ("property".to_string(), names(&s.property)),
+            ("torn".to_string(), names(&s.torn)),
+            ("user".to_string(), names(&s.user)),
+            ("racing".to_string(), names(&s.racing)),
+            ("forum".to_string(), names(&s.forum)),
+            ("key".to_string(), names(&s.key)),
+        ]);
+
+        Self {
+            level: info.access.level.clamp(0, u8::MAX as i32) as u8,
+            faction_id: info
+                .access
+                .faction
+                .then_some(info.user.faction_id)
+                .flatten()
+                .map(|id| id.0 as u64),
+            configured_factions: Vec::new(),
+            selections,
+        }
+    }
+
+    pub fn is_known(&self) -> bool {
+        self.level > 0
+    }
+
+    /// Whether a request for `route` may be sent with this key.
+    ///
+    /// A key that was not inspected yet is only trusted with what it was configured
+    /// for: a faction key (e.g. a revive source's faction leader key) serves requests
+    /// for the factions it was configured for and stays out of the public rotation,
+    /// while a key without a faction (the dev test key, a donated key that passed a
+    /// profile lookup) is tried for anything.
+    pub(crate) fn allows(&self, route: &Route, faction: Option<u64>) -> bool {
+        if !self.is_known() {
+            return match faction {
+                Some(faction) => {
+                    self.faction_id == Some(faction) || self.configured_factions.contains(&faction)
+                }
+                None => self.faction_id.is_none(),
+            };
+        }
+        if faction.is_some() && self.faction_id != faction {
+            return false;
+        }
+        self.selections
+            .get(&route.scope)
+            .is_some_and(|allowed| allowed.contains(&route.selection))
+    }
+}
+
+/// The scope and selection a request path asks for, e.g. `/faction/123/basic` is
+/// `faction` / `basic`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Route {
+    pub scope: String,
+    pub selection: String,
+}
+
+impl Route {
+    pub fn from_path(path: &str) -> Self {
+        let mut segments = path.split('/').filter(|s| !s.is_empty());
+        let scope = segments.next().unwrap_or_default().to_lowercase();
+        let selection = segments.next_back().unwrap_or_default().to_lowercase();
+        Self { scope, selection }
+    }
+}
+
+impl std::fmt::Display for Route {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.scope, self.selection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(path: &str) -> Route {
+        Route::from_path(path)
+    }
+
+    #[test]
+    fn uninspected_faction_key_only_serves_its_faction() {
+        let access = KeyAccess::factions(&[7]);
+        assert!(access.allows(&route("/faction/revivesFull"), Some(7)));
+        assert!(!access.allows(&route("/faction/revivesFull"), Some(8)));
+        assert!(!access.allows(&route("/user/123/profile"), None));
+    }
+
+    #[test]
+    fn uninspected_source_key_serves_every_configured_faction() {
+        let access = KeyAccess::factions(&[7, 8]);
+        assert!(access.allows(&route("/faction/revivesFull"), Some(7)));
+        assert!(access.allows(&route("/faction/revivesFull"), Some(8)));
+        assert!(!access.allows(&route("/faction/revivesFull"), Some(9)));
+        assert!(!access.allows(&route("/user/123/profile"), None));
+    }
+
+    #[test]
+    fn uninspected_key_without_faction_serves_public_routes() {
+        let access = KeyAccess::default();
+        assert!(access.allows(&route("/user/123/profile"), None));
+        assert!(!access.allows(&route("/faction/revivesFull"), Some(7)));
+    }
+
+    #[test]
+    fn inspected_key_serves_its_selections() {
+        let access = KeyAccess {
+            level: 1,
+            faction_id: None,
+            configured_factions: Vec::new(),
+            selections: BTreeMap::from([("user".to_string(), vec!["profile".to_string()])]),
+        };
+        assert!(access.allows(&route("/user/123/profile"), None));
+        assert!(!access.allows(&route("/user/123/faction"), None));
+    }
+}
@@ -0,0 +1,186 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use mongodb::bson::{self, doc};
+
+use crate::database::structures::APIKey as DbAPIKey;
+use crate::database::Database;
+use crate::shutdown::Shutdown;
+use crate::torn_api::client::{is_key_error, RemovedKey};
+use crate::torn_api::{APIKey, TornAPI, TornError};
+use torn_api::executor::ExecutorExt;
+
+/// How often every stored key is checked again.
+const CHECK_INTERVAL: Duration = Duration::from_secs(6 * 3600);
+
+/// Pause between two keys, so a check spreads out instead of bursting.
+const KEY_SPACING: Duration = Duration::from_secs(2);
+
+/// How often request counts of the rotation are added to the stored keys.
+const USAGE_FLUSH_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+impl TornAPI {
+    /// Checks every key in `api_keys` on a schedule, keeping the stored owner, access
+    /// and validity current and the running rotation in line with it.
+    pub async fn run_key_health(self: Arc<Self>, shutdown: Shutdown) {
+        log::info!("Starting API key health check loop");
+
+        loop {
+            self.check_stored_keys(&shutdown).await;
+            if !shutdown.sleep(CHECK_INTERVAL).await {
+                break;
+            }
+        }
+
+        log::info!("API key health check loop stopped");
+    }
+
+    /// Stores request counts of the rotation's keys every few minutes, and once more on
+    /// shutdown. Holds a unit of work for its whole life so shutdown waits for that
+    /// last flush.
+    pub async fn run_usage_flush(self: Arc<Self>, shutdown: Shutdown) {
+        let Some(_work) = shutdown.begin() else {
+            return;
+        };
+
+        loop {
+            let running = shutdown.sleep(USAGE_FLUSH_INTERVAL).await;
+            self.flush_usage().await;
+            if !running {
+                break;
+            }
+        }
+    }
+
+    async fn check_stored_keys(&self, shutdown: &Shutdown) {
+        let keys: Vec<DbAPIKey> = match Database::get_collection().await {
+            Ok(keys) => keys,
+            Err(e) => {
+                log::error!("Key health check could not load api_keys: {e:#}");
+                return;
+            }
+        };
+
+        let mut removed = 0;
+        let mut restored = 0;
+
+        for key in &keys {
+            let Some(work) = shutdown.begin() else {
+                return;
+            };
+            match self.check_stored_key(key).await {
+                Some(true) if !key.valid => restored += 1,
+                Some(false) if key.valid => removed += 1,
+                _ => {}
+            }
+            drop(work);
+            if !shutdown.sleep(KEY_SPACING).await {
+                return;
+            }
+        }
+
+        log::info!(
+            "Key health check done: {} key(s), {restored} restored, {removed} removed",
+            keys.len()
+        );
+    }
+
+    /// Returns whether the key works now, `None` when that could not be determined.
+    async fn check_stored_key(&self, key: &DbAPIKey) -> Option<bool> {
+        let plain = match key.key() {
+            Ok(plain) => plain,
+            Err(e) => {
+                log::error!("Key health check: cannot read key of {}: {e:#}", key.name);
+                return None;
+            }
+        };
+
+        let probe = self.probe(&plain);
+        let profile = match probe.user().profile(|b| b).await {
+            Ok(resp) => resp.profile,
+            Err(TornError::Api(e)) if is_key_error(e.code()) => {
+                if key.valid {
+                    log::warn!("Key health check: key of {} was rejected ({e})", key.name);
+                    self.remove_key(key.id);
+                    self.record_removal(RemovedKey {
+                        owner: key.name.clone(),
+                        db_id: Some(key.id),
+                        code: e.code(),
+                        message: e.to_string(),
+                    })
+                    .await;
+                }
+                return Some(false);
+            }
+            Err(e) => {
+                log::warn!(
+                    "Key health check: could not check key of {}: {e:#}",
+                    key.name
+                );
+                return None;
+            }
+        };
+
+        let access = match self.inspect_key(&plain).await {
+            Ok(access) => access,
+            Err(e) => {
+                log::warn!(
+                    "Key health check: could not inspect key of {}: {e:#}",
+                    key.name
+                );
+                key.access.clone().unwrap_or_default()
+            }
+        };
+
+        let owner_id = profile.id.0 as u64;
+        let result = Database::update_doc::<DbAPIKey>(
+            doc! { "_id": key.id },
+            doc! {
+                "$set": {
+                    "name": &profile.name,
+                    "owner_id": owner_id as i64,
+                    "valid": true,
+                    "access": bson::to_bson(&access).unwrap_or_default(),
+                },
+                "$unset": { "error_code": "", "error_message": "", "invalidated_at": "" },
+            },
+        )
+        .await;
+        if let Err(e) = result {
+            log::warn!(
+                "Key health check: failed to update key of {}: {e:#}",
+                key.name
+            );
+            return Some(true);
+        }
+
+        // The donor may have revoked the key while it was being checked.
+        let still_stored =
+            Database::get_collection_with_filter::<DbAPIKey>(Some(doc! { "_id": key.id }))
+                .await
+                .map(|found| !found.is_empty())
+                .unwrap_or(false);
+        if !still_stored {
+            return Some(true);
+        }
+
+        if !key.valid {
+            log::info!(
+                "Key health check: key of {} works again, adding it back",
+                profile.name
+            );
+        }
+
+        self.add_key(APIKey {
+            key: plain,
+            rate_limit: key.rate_limit(),
+            owner: profile.name,
+            owner_id: Some(owner_id),
+            db_id: Some(key.id),
+            access,
+        })
+        .await;
+
+        Some(true)
+    }
+}
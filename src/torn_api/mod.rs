@@ -1,5 +1,16 @@
-mod revive_monitor;
+mod circuit_breaker;
 pub(crate) mod client;
+mod error;
+mod key_access;
+mod key_health;
+mod revive_monitor;
+mod priority;
+mod rate_limit;
+mod replay;
 
-pub use client::{APIKey, TornAPI};
-pub use revive_monitor::{ReviveMonitor, ReviveSourceConfig};
\ No newline at end of file
+pub use client::{APIKey, RemovedKey, TornAPI};
+pub use error::TornError;
+pub use key_access::KeyAccess;
+pub use priority::Priority;
+pub use replay::Replay;
+pub use revive_monitor::{MonitorAlert, ReviveMonitor, ReviveSourceConfig};
\ No newline at end of file
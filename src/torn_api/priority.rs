@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+/// How urgently a Torn request needs a key.
+///
+/// Higher lanes jump ahead of lower ones when keys are exhausted, and every rate
+/// window keeps a share of its capacity that lower lanes are not allowed to touch,
+/// so a `/report` building hundreds of lookups cannot starve a `/reviveme`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// Large batches of lookups, e.g. player names for a report.
+    Bulk,
+    /// Periodic work nobody is actively waiting on, e.g. revive syncing.
+    Background,
+    /// A user is waiting on a slash command or component interaction.
+    Interactive,
+}
+
+impl Priority {
+    /// Total time a request may spend waiting for keys and retrying before it gives up.
+    pub(crate) fn deadline(self) -> Duration {
+        match self {
+            // Leave room to answer the interaction before Discord gives up on it.
+            Self::Interactive => Duration::from_secs(10),
+            Self::Bulk => Duration::from_secs(90),
+            Self::Background => Duration::from_secs(300),
+        }
+    }
+
+    /// Failed attempts (network errors, transient Torn errors) tolerated before giving up.
+    pub(crate) fn max_attempts(self) -> u32 {
+        match self {
+            Self::Interactive => 3,
+            Self::Bulk => 4,
+            Self::Background => 6,
+        }
+    }
+
+    /// Percentage of every rate window held back from this lane for higher ones.
+    fn reserved_percent(self) -> u32 {
+        match self {
+            Self::Bulk => 30,
+            Self::Background => 10,
+            Self::Interactive => 0,
+        }
+    }
+
+    /// Slots of a window with the given limit this lane must leave free.
+    pub(crate) fn reserved_slots(self, limit: u32) -> u32 {
+        limit * self.reserved_percent() / 100
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::torn_api::Priority;
+
+/// Length of the window Torn counts requests over.
+pub(crate) const WINDOW: Duration = Duration::from_secs(60);
+
+/// Torn allows 100 requests per minute per player, shared by all of that player's keys.
+pub(crate) const OWNER_RATE_LIMIT: u32 = 100;
+
+/// Sliding one-minute window over the timestamps of recent requests.
+///
+/// Unlike resetting a counter on a fixed boundary, this never lets more than `limit`
+/// requests through in *any* 60 second span.
+pub(crate) struct RateWindow {
+    limit: u32,
+    hits: VecDeque<Instant>,
+}
+
+impl RateWindow {
+    pub fn new(limit: u32) -> Self {
+        Self {
+            limit,
+            hits: VecDeque::with_capacity(limit as usize),
+        }
+    }
+
+    fn prune(&mut self, now: Instant) {
+        while let Some(oldest) = self.hits.front() {
+            if now.duration_since(*oldest) >= WINDOW {
+                self.hits.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Time until a request of the given lane fits in the window, zero if one fits right now.
+    ///
+    /// Lower lanes see a smaller limit, leaving the reserved slots to higher ones. A limit
+    /// of 0 disables the window: nothing fits, ever.
+    pub fn wait(&mut self, now: Instant, priority: Priority) -> Duration {
+        self.prune(now);
+        if self.limit == 0 {
+            return WINDOW;
+        }
+        let limit = self
+            .limit
+            .saturating_sub(priority.reserved_slots(self.limit))
+            .max(1);
+        if (self.hits.len() as u32) < limit {
+            return Duration::ZERO;
+        }
+
+        // The lane's share is full: a slot frees up once enough of the oldest hits age out.
+        let freeing = self.hits.len() - limit as usize;
+        match self.hits.get(freeing) {
+            Some(hit) => (*hit + WINDOW).saturating_duration_since(now),
+            None => WINDOW,
+        }
+    }
+
+    /// Requests made in the last minute.
+    pub fn used(&mut self, now: Instant) -> usize {
+        self.prune(now);
+        self.hits.len()
+    }
+
+    pub fn limit(&self) -> u32 {
+        self.limit
+    }
+
+    pub fn set_limit(&mut self, limit: u32) {
+        self.limit = limit;
+    }
+
+    pub fn record(&mut self, now: Instant) {
+        self.hits.push_back(now);
+    }
+
+    /// Marks the window as used up, e.g. after Torn said the limit was hit by
+    /// requests made outside this bot.
+    pub fn fill(&mut self, now: Instant) {
+        self.prune(now);
+        while (self.hits.len() as u32) < self.limit {
+            self.hits.push_back(now);
+        }
+    }
+}
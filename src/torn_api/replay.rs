@@ -0,0 +1,281 @@
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::Context as _;
+use torn_api::request::{ApiRequest, ApiResponse};
+
+/// Serves recorded Torn responses from disk instead of calling Torn, so everything
+/// built on [`TornAPI`](crate::torn_api::TornAPI) can run without network access.
+///
+/// Fixtures are looked up by selection and id:
+/// - `/user/123/basic` → `user/basic/123.json`, falling back to `user/basic/default.json`
+/// - `/faction/revivesFull` → `faction/revivesFull.json`
+///
+/// Like Torn, `revivesFull` answers only with the revives inside the requested
+/// `from`/`to` window (both inclusive), in the requested `sort` order and cut to `limit`,
+/// so syncs and backfills page through a fixture the way they page through Torn.
+///
+/// An optional `errors.toml` scripts Torn error codes. Each entry maps a fixture name
+/// (`"user/profile"` or `"user/profile/123"`) to the codes returned, in order, by the
+/// next matching requests before the fixture itself is served:
+///
+/// ```toml
+/// "faction/revivesFull" = [17, 17]
+/// "user/profile/123" = [2]
+/// ```
+pub struct Replay {
+    dir: PathBuf,
+    scripted: Mutex<HashMap<String, VecDeque<u16>>>,
+}
+
+impl Replay {
+    pub fn load(dir: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let dir = dir.into();
+        if !dir.is_dir() {
+            anyhow::bail!("Replay directory '{}' does not exist", dir.display());
+        }
+
+        let errors_path = dir.join("errors.toml");
+        let scripted = if errors_path.exists() {
+            let raw = std::fs::read_to_string(&errors_path)
+                .with_context(|| format!("Failed to read '{}'", errors_path.display()))?;
+            let parsed: HashMap<String, Vec<u16>> = toml::from_str(&raw)
+                .with_context(|| format!("Failed to parse '{}'", errors_path.display()))?;
+            parsed
+                .into_iter()
+                .map(|(name, codes)| (name, codes.into()))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
+        Ok(Self {
+            dir,
+            scripted: Mutex::new(scripted),
+        })
+    }
+
+    /// Answers a request the way Torn would, from the fixtures.
+    pub(crate) fn respond(&self, request: &ApiRequest) -> ApiResponse {
+        let (selection, id) = fixture_name(&request.path);
+
+        if let Some(code) = self.next_scripted_error(&selection, id.as_deref()) {
+            log::info!("Replay: scripted error {code} for {}", request.path);
+            return error_response(code, "Scripted replay error");
+        }
+
+        let candidates = match &id {
+            Some(id) => vec![
+                self.dir.join(&selection).join(format!("{id}.json")),
+                self.dir.join(&selection).join("default.json"),
+            ],
+            None => vec![self.dir.join(format!("{selection}.json"))],
+        };
+
+        for path in &candidates {
+            if let Some(mut body) = read_fixture(path) {
+                if selection.ends_with("/revivesFull") {
+                    body = window_revives(&body, &request.parameters).unwrap_or(body);
+                }
+                return ApiResponse {
+                    status: reqwest::StatusCode::OK,
+                    body: Some(body.into()),
+                };
+            }
+        }
+
+        log::warn!("Replay: no fixture for {} (looked for {candidates:?})", request.path);
+        // Torn's answer for ids it does not know.
+        error_response(6, "Incorrect ID (no replay fixture)")
+    }
+
+    fn next_scripted_error(&self, selection: &str, id: Option<&str>) -> Option<u16> {
+        let mut scripted = self.scripted.lock().expect("replay script mutex poisoned");
+
+        let specific = id.map(|id| format!("{selection}/{id}"));
+        for name in specific.iter().map(String::as_str).chain([selection]) {
+            if let Some(code) = scripted.get_mut(name).and_then(VecDeque::pop_front) {
+                return Some(code);
+            }
+        }
+        None
+    }
+}
+
+/// Splits a request path into its fixture name (`scope/selection`) and optional id.
+fn fixture_name(path: &str) -> (String, Option<String>) {
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    match segments.as_slice() {
+        [scope, id, selection] => (format!("{scope}/{selection}"), Some(id.to_string())),
+        _ => (segments.join("/"), None),
+    }
+}
+
+fn read_fixture(path: &Path) -> Option<Vec<u8>> {
+    std::fs::read(path).ok()
+}
+
+/// Applies a `revivesFull` request's `from`, `to`, `sort` and `limit` to a fixture body.
+/// `None` when the fixture is not a revives list, which is then served as recorded.
+fn window_revives(body: &[u8], parameters: &[(&'static str, String)]) -> Option<Vec<u8>> {
+    let parameter = |name: &str| {
+        parameters
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, value)| value.as_str())
+    };
+    let from = parameter("from").and_then(|v| v.parse::<u64>().ok());
+    let to = parameter("to").and_then(|v| v.parse::<u64>().ok());
+    let limit = parameter("limit").and_then(|v| v.parse::<usize>().ok());
+    let sort = parameter("sort").map(str::to_lowercase);
+
+    let mut json: serde_json::Value = serde_json::from_slice(body).ok()?;
+    let revives = json.get_mut("revives")?.as_array_mut()?;
+
+    let timestamp = |revive: &serde_json::Value| revive["timestamp"].as_u64().unwrap_or(0);
+    revives.retain(|revive| {
+        let timestamp = timestamp(revive);
+        from.is_none_or(|from| timestamp >= from) && to.is_none_or(|to| timestamp <= to)
+    });
+    match sort.as_deref() {
+        Some("asc") => revives.sort_by_key(timestamp),
+        Some("desc") => revives.sort_by_key(|revive| std::cmp::Reverse(timestamp(revive))),
+        _ => {}
+    }
+    if let Some(limit) = limit {
+        revives.truncate(limit);
+    }
+
+    serde_json::to_vec(&json).ok()
+}
+
+fn error_response(code: u16, message: &str) -> ApiResponse {
+    let body = serde_json::json!({ "error": { "code": code, "error": message } }).to_string();
+    ApiResponse {
+        status: reqwest::StatusCode::OK,
+        body: Some(body.into_bytes().into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh fixture directory under the system temp dir.
+    fn fixture_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("deathfr-replay-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("faction")).unwrap();
+        dir
+    }
+
+    fn request(path: &str, parameters: Vec<(&'static str, String)>) -> ApiRequest {
+        ApiRequest {
+            path: path.to_string(),
+            parameters,
+        }
+    }
+
+    fn body_json(response: &ApiResponse) -> serde_json::Value {
+        serde_json::from_slice(response.body.as_deref().unwrap()).unwrap()
+    }
+
+    fn revive_timestamps(json: &serde_json::Value) -> Vec<u64> {
+        json["revives"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|revive| revive["timestamp"].as_u64().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn fixture_names() {
+        assert_eq!(
+            fixture_name("/user/123/basic"),
+            ("user/basic".to_string(), Some("123".to_string()))
+        );
+        assert_eq!(fixture_name("/faction/revivesFull"), ("faction/revivesFull".to_string(), None));
+        assert_eq!(fixture_name("/key/info"), ("key/info".to_string(), None));
+    }
+
+    #[test]
+    fn id_fixture_falls_back_to_default() {
+        let dir = fixture_dir("default");
+        std::fs::create_dir_all(dir.join("user/basic")).unwrap();
+        std::fs::write(dir.join("user/basic/1.json"), r#"{"profile":{"id":1}}"#).unwrap();
+        std::fs::write(dir.join("user/basic/default.json"), r#"{"profile":{"id":0}}"#).unwrap();
+        let replay = Replay::load(&dir).unwrap();
+
+        let own = replay.respond(&request("/user/1/basic", vec![]));
+        assert_eq!(body_json(&own)["profile"]["id"], 1);
+        let fallback = replay.respond(&request("/user/2/basic", vec![]));
+        assert_eq!(body_json(&fallback)["profile"]["id"], 0);
+        let missing = replay.respond(&request("/faction/3/basic", vec![]));
+        assert_eq!(body_json(&missing)["error"]["code"], 6);
+    }
+
+    #[test]
+    fn revives_follow_the_requested_window() {
+        let dir = fixture_dir("window");
+        let revives: Vec<serde_json::Value> = [30, 10, 20, 40]
+            .into_iter()
+            .map(|timestamp| serde_json::json!({ "id": timestamp, "timestamp": timestamp }))
+            .collect();
+        std::fs::write(
+            dir.join("faction/revivesFull.json"),
+            serde_json::json!({ "revives": revives, "_metadata": {} }).to_string(),
+        )
+        .unwrap();
+        let replay = Replay::load(&dir).unwrap();
+
+        let page = replay.respond(&request(
+            "/faction/revivesFull",
+            vec![
+                ("from", "20".to_string()),
+                ("to", "40".to_string()),
+                ("sort", "Asc".to_string()),
+                ("limit", "2".to_string()),
+            ],
+        ));
+        let json = body_json(&page);
+        assert_eq!(revive_timestamps(&json), vec![20, 30]);
+        assert!(json.get("_metadata").is_some());
+
+        let newest_first = replay.respond(&request(
+            "/faction/revivesFull",
+            vec![("from", "15".to_string()), ("sort", "Desc".to_string())],
+        ));
+        assert_eq!(revive_timestamps(&body_json(&newest_first)), vec![40, 30, 20]);
+    }
+
+    #[test]
+    fn scripted_errors_come_first_and_in_order() {
+        let dir = fixture_dir("errors");
+        std::fs::write(dir.join("faction/revivesFull.json"), r#"{"revives":[]}"#).unwrap();
+        std::fs::write(
+            dir.join("errors.toml"),
+            "\"faction/revivesFull\" = [17, 5]\n\"user/profile/7\" = [2]\n",
+        )
+        .unwrap();
+        let replay = Replay::load(&dir).unwrap();
+
+        let code = |response: ApiResponse| body_json(&response)["error"]["code"].as_u64();
+        let revives = || request("/faction/revivesFull", vec![]);
+        assert_eq!(code(replay.respond(&revives())), Some(17));
+        assert_eq!(code(replay.respond(&revives())), Some(5));
+        assert_eq!(code(replay.respond(&revives())), None);
+
+        // Only the scripted id gets the error; other ids go straight to the fixtures.
+        assert_eq!(code(replay.respond(&request("/user/8/profile", vec![]))), Some(6));
+        assert_eq!(code(replay.respond(&request("/user/7/profile", vec![]))), Some(2));
+    }
+
+    #[test]
+    fn malformed_errors_file_is_rejected() {
+        let dir = fixture_dir("malformed");
+        std::fs::write(dir.join("errors.toml"), "\"user/profile\" = \"two\"\n").unwrap();
+        assert!(Replay::load(&dir).is_err());
+    }
+}
@@ -1,57 +1,222 @@
+use crate::database::structures::{BackfillStatus, ReviveBackfill, ReviveEntry};
 use crate::database::Database;
-use crate::torn_api::TornAPI;
+use crate::shutdown::Shutdown;
+use crate::torn_api::{APIKey, KeyAccess, TornAPI};
+use mongodb::bson::doc;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
 
+/// Requests per minute a revive source key may make, leaving the rest of the faction
+/// leader's budget to their own tools.
+const REVIVE_KEY_RATE_LIMIT: u32 = 2;
+
+/// A configured revive key and the factions whose revives are synced.
+///
+/// Torn only serves revives of the key owner's own faction, so every faction listed
+/// needs some key in the rotation with faction access to it — this one, another
+/// source's, or a donated key. Each faction keeps its own sync cursor.
 #[derive(Debug, Clone)]
 pub struct ReviveSourceConfig {
     pub api_key: String,
     pub faction_ids: Vec<u64>,
 }
 
-struct ReviveSource {
-    api: Arc<TornAPI>,
-    faction_ids: Vec<u64>,
+impl ReviveSourceConfig {
+    /// The source key as a member of the shared [`TornAPI`] rotation, restricted to
+    /// its configured factions until Torn tells us what it may access.
+    pub fn rotation_key(&self) -> APIKey {
+        let faction_id = self.faction_ids.first().copied().unwrap_or_default();
+        APIKey {
+            key: self.api_key.clone(),
+            rate_limit: REVIVE_KEY_RATE_LIMIT,
+            owner: format!("Revive Monitor Key ({faction_id})"),
+            owner_id: None,
+            db_id: None,
+            access: KeyAccess::factions(&self.faction_ids),
+        }
+    }
+}
+
+/// Revives requested per page; Torn's maximum.
+const PAGE_SIZE: u32 = 1000;
+
+/// Pages fetched per faction in one sync, so one faction with a huge history can't
+/// hold the sync lock for too long. The next sync continues where this one stopped.
+const MAX_PAGES_PER_SYNC: u32 = 50;
+
+/// Poll interval while no contract needs fresh data.
+const IDLE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Poll interval while a contract is running or close to its start or end.
+const CONTRACT_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Poll interval while the last sync stopped before catching up.
+const BACKLOG_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long before its start and after its end a contract still counts as running.
+const CONTRACT_MARGIN: Duration = Duration::from_secs(3600);
+
+/// How often a waiting loop looks at contracts again.
+const STATE_CHECK_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Failed syncs of a faction in a row before the owner is alerted.
+const FAILURE_ALERT_THRESHOLD: u32 = 3;
+
+/// Age of stored revives at which they count as stale: three missed idle polls.
+const STALE_AFTER: Duration = Duration::from_secs(3 * 3600);
+
+/// Something about revive syncing that needs the owner's attention.
+#[derive(Debug, Clone)]
+pub enum MonitorAlert {
+    /// Syncing the faction failed `failures` times in a row.
+    SyncFailing {
+        faction_id: u64,
+        failures: u32,
+        error: String,
+    },
+    /// A faction that was alerted about synced again.
+    SyncRecovered { faction_id: u64, failures: u32 },
+    /// Revives of the faction are only stored up to `synced_until`.
+    Stale { faction_id: u64, synced_until: u64 },
+    /// Revives of a faction that was stale are current again.
+    StaleRecovered { faction_id: u64 },
+    /// The sync loop itself has not completed a round since `last_update`.
+    LoopStalled { last_update: u64 },
 }
 
-struct SourceSyncResult {
+struct FactionSyncResult {
     inserted: usize,
-    has_backlog: bool,
+    /// Unix time up to which every revive of the faction is stored.
+    synced_until: u64,
 }
 
 pub struct SyncResult {
     pub total_inserted: usize,
-    pub has_backlog: bool,
+    /// Unix time up to which revives of *every* faction are stored.
+    pub synced_until: u64,
+}
+
+impl SyncResult {
+    /// How far the stored revives trail behind now.
+    pub fn behind(&self) -> Duration {
+        let now = chrono::Utc::now().timestamp().max(0) as u64;
+        Duration::from_secs(now.saturating_sub(self.synced_until))
+    }
+
+    /// Whether the last sync left no revives unfetched (short of a minute of slack
+    /// for revives made while it ran).
+    pub fn caught_up(&self) -> bool {
+        self.behind() < Duration::from_secs(60)
+    }
+}
+
+/// One page of an import of older revives.
+pub struct BackfillPage {
+    pub inserted: usize,
+    /// Where the next page starts.
+    pub next: u64,
+    /// Whether every revive up to the end of the window is stored.
+    pub finished: bool,
 }
 
 pub struct ReviveMonitor {
-    sources: Vec<ReviveSource>,
+    api: Arc<TornAPI>,
+    /// Every faction of every source, once.
+    faction_ids: Vec<u64>,
     sync_lock: tokio::sync::Mutex<()>,
+    new_revives: broadcast::Sender<Vec<ReviveEntry>>,
+    /// Factions a backfill finished storing older revives for.
+    backfilled: broadcast::Sender<u64>,
+    /// Failed syncs in a row, per faction.
+    failures: std::sync::Mutex<HashMap<u64, u32>>,
+    alerts: broadcast::Sender<MonitorAlert>,
 }
 
 impl ReviveMonitor {
-    pub fn new(configs: Vec<ReviveSourceConfig>) -> Self {
-        let sources = configs
-            .into_iter()
-            .map(|config| {
-                let api = TornAPI::new(vec![crate::torn_api::APIKey {
-                    key: config.api_key,
-                    rate_limit: 2,
-                    owner: "Revive Monitor Key".to_string(),
-                }]);
-                ReviveSource {
-                    api: Arc::new(api),
-                    faction_ids: config.faction_ids,
-                }
-            })
-            .collect();
+    /// The source keys must already be part of `api`'s rotation (see
+    /// [`ReviveSourceConfig::rotation_key`]).
+    pub fn new(api: Arc<TornAPI>, configs: Vec<ReviveSourceConfig>) -> Self {
+        let mut faction_ids = Vec::new();
+        for id in configs.into_iter().flat_map(|config| config.faction_ids) {
+            if !faction_ids.contains(&id) {
+                faction_ids.push(id);
+            }
+        }
 
         Self {
-            sources,
+            api,
+            faction_ids,
             sync_lock: tokio::sync::Mutex::new(()),
+            new_revives: broadcast::channel(64).0,
+            backfilled: broadcast::channel(16).0,
+            failures: std::sync::Mutex::new(HashMap::new()),
+            alerts: broadcast::channel(16).0,
         }
     }
 
+    /// Subscribe to failures and staleness of revive syncing.
+    pub fn subscribe_alerts(&self) -> broadcast::Receiver<MonitorAlert> {
+        self.alerts.subscribe()
+    }
+
+    fn alert(&self, alert: MonitorAlert) {
+        log::warn!("Revive monitor alert: {alert:?}");
+        let _ = self.alerts.send(alert);
+    }
+
+    fn record_failure(&self, faction_id: u64, error: &anyhow::Error) {
+        let failures = {
+            let mut all = self.failures.lock().unwrap();
+            let count = all.entry(faction_id).or_default();
+            *count += 1;
+            *count
+        };
+
+        // Once, not on every failure after the threshold.
+        if failures == FAILURE_ALERT_THRESHOLD {
+            self.alert(MonitorAlert::SyncFailing {
+                faction_id,
+                failures,
+                error: format!("{error:#}"),
+            });
+        }
+    }
+
+    fn record_success(&self, faction_id: u64) {
+        let failures = self
+            .failures
+            .lock()
+            .unwrap()
+            .remove(&faction_id)
+            .unwrap_or(0);
+
+        if failures >= FAILURE_ALERT_THRESHOLD {
+            self.alert(MonitorAlert::SyncRecovered {
+                faction_id,
+                failures,
+            });
+        }
+    }
+
+    /// Subscribe to revives stored for the first time, one batch per page.
+    pub fn subscribe_revives(&self) -> broadcast::Receiver<Vec<ReviveEntry>> {
+        self.new_revives.subscribe()
+    }
+
+    /// Subscribe to finished backfills that stored revives, by faction. Backfilled
+    /// revives are not sent to [`Self::subscribe_revives`].
+    pub fn subscribe_backfills(&self) -> broadcast::Receiver<u64> {
+        self.backfilled.subscribe()
+    }
+
+    /// Tells subscribers a backfill of `faction_id` finished after storing revives.
+    pub fn backfill_finished(&self, faction_id: u64) {
+        // Nobody listening is not an error.
+        let _ = self.backfilled.send(faction_id);
+    }
+
     fn last_revive_key(faction_id: u64) -> String {
         format!("last_revive_{faction_id}")
     }
@@ -67,46 +232,155 @@ impl ReviveMonitor {
         Ok(())
     }
 
-    async fn sync_source(source: &ReviveSource) -> anyhow::Result<SourceSyncResult> {
-        let primary_faction = source
-            .faction_ids
-            .first()
-            .copied()
-            .ok_or_else(|| anyhow::anyhow!("Revive source has no faction IDs configured"))?;
+    fn synced_until_key(faction_id: u64) -> String {
+        format!("synced_until_{faction_id}")
+    }
 
-        let last_revive = Self::get_last_revive(primary_faction).await;
+    async fn get_synced_until(faction_id: u64) -> u64 {
+        Database::get_value(&Self::synced_until_key(faction_id))
+            .await
+            .unwrap_or(0)
+    }
 
-        let response = match source.api.get_revives_full(last_revive).await {
-            Ok(resp) => resp,
-            Err(e) => {
-                log::error!("Failed to collect revives for faction {primary_faction}: {e:#}");
-                return Ok(SourceSyncResult {
-                    inserted: 0,
-                    has_backlog: false,
-                });
+    /// Pages through the faction's revives from its cursor until a page comes back
+    /// short, which means everything up to the time of that request is stored.
+    async fn sync_faction(&self, faction_id: u64) -> anyhow::Result<FactionSyncResult> {
+        let mut cursor = Self::get_last_revive(faction_id).await;
+        let mut synced_until = Self::get_synced_until(faction_id).await;
+        let mut inserted = 0;
+
+        for page in 1..=MAX_PAGES_PER_SYNC {
+            let requested_at = chrono::Utc::now().timestamp().max(0) as u64;
+
+            // Pages before a failure keep their cursor; the next sync continues from there.
+            let response = self
+                .api
+                .get_revives_full(faction_id, cursor, None, PAGE_SIZE)
+                .await?;
+
+            let revives: Vec<ReviveEntry> =
+                response.revives.into_iter().map(Into::into).collect();
+            let len = revives.len();
+
+            if let Some(newest) = revives.last().map(|r| r.timestamp) {
+                // `from` is inclusive and a page can end partway through a second, so the
+                // cursor stays on the newest second and its revives are fetched again
+                // next time. Only newly stored ones count, or a caught up faction would
+                // look like it is still making progress.
+                let new = Database::insert_manny(revives).await?;
+                inserted += new.len();
+                // Nobody listening (no log channel) is not an error.
+                if !new.is_empty() {
+                    let _ = self.new_revives.send(new);
+                }
+
+                // A full page of revives from a single second would be fetched forever;
+                // step past that second instead.
+                let next = if newest > cursor { newest } else { cursor + 1 };
+                if newest <= cursor && len as u32 >= PAGE_SIZE {
+                    log::warn!(
+                        "A full page of revives of faction {faction_id} at {newest}, moving the cursor past it"
+                    );
+                }
+                cursor = next;
+                Self::set_last_revive(faction_id, cursor).await?;
             }
-        };
 
-        let revives: Vec<crate::database::structures::ReviveEntry> =
-            response.revives.into_iter().map(Into::into).collect();
+            if (len as u32) < PAGE_SIZE {
+                // A backfill the cursor skipped ahead for still has to import its gap.
+                synced_until = match Self::open_gap(faction_id).await? {
+                    Some(gap) => requested_at.min(gap.cursor),
+                    None => requested_at,
+                };
+                Database::set_value(&Self::synced_until_key(faction_id), synced_until).await?;
+                log::info!(
+                    "Revives of faction {faction_id} caught up after {page} page(s), {inserted} collected"
+                );
+                break;
+            }
 
-        let len = revives.len();
-        let has_backlog = len > 900;
+            if page == MAX_PAGES_PER_SYNC {
+                log::info!(
+                    "Revives of faction {faction_id}: {inserted} collected, more remain after {cursor}"
+                );
+            }
+        }
 
-        if len > 0 {
-            let newest_timestamp = revives.last().unwrap().timestamp;
-            Database::insert_manny(revives).await?;
-            Self::set_last_revive(primary_faction, newest_timestamp).await?;
-            log::info!(
-                "Collected {len} revives for faction {primary_faction}, newest revive: {newest_timestamp}"
-            );
-        } else {
-            log::info!("No new revives found for faction {primary_faction}.");
+        Ok(FactionSyncResult {
+            inserted,
+            synced_until,
+        })
+    }
+
+    /// Whether revives of `faction_id` are synced by this monitor.
+    pub fn covers_faction(&self, faction_id: u64) -> bool {
+        self.faction_ids.contains(&faction_id)
+    }
+
+    /// Lets a backfill of `from..to` take over history the regular sync has not reached
+    /// yet: the faction's cursor jumps to `to` when the backfill covers everything it
+    /// skips, or when the faction was never synced and would otherwise start from 1.
+    ///
+    /// Returns whether the cursor moved. If so, the faction counts as synced only up to
+    /// the backfill's progress until it finishes (see [`Self::open_gap`]).
+    pub async fn hand_over_to_backfill(
+        &self,
+        faction_id: u64,
+        from: u64,
+        to: u64,
+    ) -> anyhow::Result<bool> {
+        let _guard = self.sync_lock.lock().await;
+
+        let cursor = Self::get_last_revive(faction_id).await;
+        if cursor < to && (cursor <= 1 || cursor >= from) {
+            log::info!("Revive cursor of faction {faction_id} moved from {cursor} to {to} for a backfill");
+            Self::set_last_revive(faction_id, to).await?;
+
+            let synced_until = Self::get_synced_until(faction_id).await;
+            if synced_until > from {
+                Database::set_value(&Self::synced_until_key(faction_id), from).await?;
+            }
+            return Ok(true);
         }
+        Ok(false)
+    }
+
+    /// The earliest unfinished backfill the faction's cursor skipped ahead for, running
+    /// or failed. Revives of the faction are only complete up to its cursor.
+    async fn open_gap(faction_id: u64) -> anyhow::Result<Option<ReviveBackfill>> {
+        let gaps = Database::get_collection_with_filter::<ReviveBackfill>(Some(doc! {
+            "faction_id": faction_id as i64,
+            "gap_open": true,
+        }))
+        .await?;
+        Ok(gaps.into_iter().min_by_key(|gap| gap.cursor))
+    }
+
+    /// Imports one page of the faction's revives from `from` up to `to`, leaving its
+    /// sync cursor alone. Old revives are not announced to the revive log.
+    pub async fn backfill_page(
+        &self,
+        faction_id: u64,
+        from: u64,
+        to: u64,
+    ) -> anyhow::Result<BackfillPage> {
+        let response = self
+            .api
+            .get_revives_full(faction_id, from, Some(to), PAGE_SIZE)
+            .await?;
+
+        let revives: Vec<ReviveEntry> = response.revives.into_iter().map(Into::into).collect();
+        let len = revives.len();
+        let newest = revives.last().map(|r| r.timestamp).unwrap_or(to);
+        let inserted = Database::insert_manny(revives).await?.len();
 
-        Ok(SourceSyncResult {
-            inserted: len,
-            has_backlog,
+        // Same single-second guard as the regular sync.
+        let next = if newest > from { newest } else { from + 1 };
+
+        Ok(BackfillPage {
+            inserted,
+            next,
+            finished: (len as u32) < PAGE_SIZE || next > to,
         })
     }
 
@@ -114,60 +388,189 @@ impl ReviveMonitor {
         let _guard = self.sync_lock.lock().await;
 
         let mut total_inserted = 0;
-        let mut has_backlog = false;
+        let mut synced_until = u64::MAX;
 
-        for source in &self.sources {
-            match Self::sync_source(source).await {
+        for faction_id in &self.faction_ids {
+            match self.sync_faction(*faction_id).await {
                 Ok(result) => {
+                    self.record_success(*faction_id);
                     total_inserted += result.inserted;
-                    has_backlog |= result.has_backlog;
+                    synced_until = synced_until.min(result.synced_until);
                 }
                 Err(e) => {
-                    log::error!("Revive source sync failed: {e:#}");
+                    log::error!("Revive sync for faction {faction_id} failed: {e:#}");
+                    self.record_failure(*faction_id, &e);
+                    synced_until = synced_until.min(Self::get_synced_until(*faction_id).await);
                 }
             }
         }
 
+        if synced_until == u64::MAX {
+            synced_until = 0;
+        }
+
         Database::set_value("last_update", chrono::Utc::now().timestamp()).await?;
 
         Ok(SyncResult {
             total_inserted,
-            has_backlog,
+            synced_until,
         })
     }
 
+    /// Syncs until every revive up to `contract_ended` is stored. Fails when the sync
+    /// stops making progress before that, so a report is never built on partial data.
     pub async fn sync_for_contract(&self, contract_ended: u64) -> anyhow::Result<SyncResult> {
         log::info!("sync_for_contract: starting sync loop for contract_ended={contract_ended}");
         let mut iteration = 0u32;
+        let mut previous_until = None;
         loop {
             iteration += 1;
             log::info!("sync_for_contract: iteration {iteration}, acquiring sync lock…");
             let result = self.sync_once().await?;
             log::info!(
-                "sync_for_contract: iteration {iteration} done — inserted={}, has_backlog={}",
-                result.total_inserted, result.has_backlog
+                "sync_for_contract: iteration {iteration} done — inserted={}, synced_until={}, behind={}s",
+                result.total_inserted,
+                result.synced_until,
+                result.behind().as_secs()
             );
-            if result.total_inserted == 0 || !result.has_backlog {
+
+            if result.synced_until >= contract_ended {
                 log::info!("sync_for_contract: finished after {iteration} iteration(s)");
                 return Ok(result);
             }
+
+            // Syncing again can't fill a gap a backfill has yet to import.
+            for faction_id in &self.faction_ids {
+                let Some(gap) = Self::open_gap(*faction_id).await? else {
+                    continue;
+                };
+                if gap.cursor >= contract_ended {
+                    continue;
+                }
+                if gap.status == BackfillStatus::Running {
+                    anyhow::bail!(
+                        "a revive backfill of faction {faction_id} is still importing revives, it has reached <t:{}:f>",
+                        gap.cursor
+                    );
+                }
+                anyhow::bail!(
+                    "a revive backfill of faction {faction_id} stopped at <t:{}:f>; start it again from that date",
+                    gap.cursor
+                );
+            }
+
+            // A faction with a long backlog moves its cursor without finishing, so only
+            // a round that neither stored revives nor moved the slowest faction is stuck.
+            let stalled = previous_until.is_some_and(|until| result.synced_until <= until);
+            if stalled && result.total_inserted == 0 {
+                anyhow::bail!(
+                    "revives are only synced up to {} ({}s before the contract ended) and syncing makes no progress",
+                    result.synced_until,
+                    contract_ended.saturating_sub(result.synced_until)
+                );
+            }
+            previous_until = Some(result.synced_until);
         }
     }
 
-    pub async fn run_loop(self: Arc<Self>) {
+    /// Whether leadership is waiting on fresh revive data: a contract is active, about
+    /// to start, or ended recently.
+    async fn contract_window_open() -> bool {
+        let now = chrono::Utc::now().timestamp();
+        let filter = doc! { "$or": [
+            { "status": "active" },
+            { "status": "pending", "started": { "$lte": now + CONTRACT_MARGIN.as_secs() as i64 } },
+            { "status": "ended", "ended": { "$gte": now - CONTRACT_MARGIN.as_secs() as i64 } },
+        ] };
+
+        match Database::get_collection_size(Some(filter)).await {
+            Ok(count) => count > 0,
+            Err(e) => {
+                log::error!("Failed to check for running contracts: {e:#}");
+                false
+            }
+        }
+    }
+
+    async fn poll_interval(backlog: bool) -> Duration {
+        if backlog {
+            BACKLOG_INTERVAL
+        } else if Self::contract_window_open().await {
+            CONTRACT_INTERVAL
+        } else {
+            IDLE_INTERVAL
+        }
+    }
+
+    pub async fn run_loop(self: Arc<Self>, shutdown: Shutdown) {
         log::info!("Starting revive monitor loop");
 
-        loop {
-            let sleep_duration = match self.sync_once().await {
-                Ok(result) if result.has_backlog => 300,
-                Ok(_) => 3600,
+        'sync: loop {
+            let Some(work) = shutdown.begin() else {
+                break;
+            };
+            let backlog = match self.sync_once().await {
+                Ok(result) => !result.caught_up(),
                 Err(e) => {
                     log::error!("Revive monitor sync failed: {e:#}");
-                    3600
+                    false
                 }
             };
+            drop(work);
+            let synced_at = Instant::now();
 
-            tokio::time::sleep(Duration::from_secs(sleep_duration)).await;
+            // Re-evaluated while waiting, so a contract starting during an idle hour
+            // shortens the wait.
+            loop {
+                let interval = Self::poll_interval(backlog).await;
+                let elapsed = synced_at.elapsed();
+                if elapsed >= interval {
+                    break;
+                }
+                if !shutdown
+                    .sleep((interval - elapsed).min(STATE_CHECK_INTERVAL))
+                    .await
+                {
+                    break 'sync;
+                }
+            }
+        }
+
+        log::info!("Revive monitor loop stopped");
+    }
+
+    /// Alerts when stored revives fall behind, whether because Torn keeps failing or
+    /// because the sync loop stopped running.
+    pub async fn run_watchdog(self: Arc<Self>, shutdown: Shutdown) {
+        let mut stale: HashSet<u64> = HashSet::new();
+        let mut alerted_last_update = None;
+
+        while shutdown.sleep(STATE_CHECK_INTERVAL).await {
+            let now = chrono::Utc::now().timestamp().max(0) as u64;
+            let is_stale = |timestamp: u64| now.saturating_sub(timestamp) > STALE_AFTER.as_secs();
+
+            let last_update = Database::get_value::<i64>("last_update")
+                .await
+                .unwrap_or(0)
+                .max(0) as u64;
+            if is_stale(last_update) && alerted_last_update != Some(last_update) {
+                alerted_last_update = Some(last_update);
+                self.alert(MonitorAlert::LoopStalled { last_update });
+            }
+
+            for &faction_id in &self.faction_ids {
+                let synced_until = Self::get_synced_until(faction_id).await;
+                if is_stale(synced_until) {
+                    if stale.insert(faction_id) {
+                        self.alert(MonitorAlert::Stale {
+                            faction_id,
+                            synced_until,
+                        });
+                    }
+                } else if stale.remove(&faction_id) {
+                    self.alert(MonitorAlert::StaleRecovered { faction_id });
+                }
+            }
         }
     }
-}
\ No newline at end of file
+}